@@ -0,0 +1,19 @@
+-- Last successful provider response per provider and location
+CREATE TABLE IF NOT EXISTS weather_cache (
+    provider   TEXT    NOT NULL,
+    -- 'current' or 'forecast'
+    kind       TEXT    NOT NULL,
+    -- Coordinates scaled by 10^precision and rounded
+    latitude   INTEGER NOT NULL,
+    longitude  INTEGER NOT NULL,
+    -- Forecast length; 0 for current weather
+    days       INTEGER NOT NULL DEFAULT 0,
+    -- JSON-encoded CurrentWeather or list of DayForecast
+    payload    TEXT    NOT NULL,
+    -- Unix seconds
+    fetched_at INTEGER NOT NULL,
+    PRIMARY KEY (provider, kind, latitude, longitude, days)
+);
+
+CREATE INDEX IF NOT EXISTS idx_weather_cache_location
+    ON weather_cache (kind, latitude, longitude, days, fetched_at);
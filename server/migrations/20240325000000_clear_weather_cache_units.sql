@@ -0,0 +1,3 @@
+-- Payloads are now stored in canonical units (m/s wind, metres visibility);
+-- rows written before that mixed provider units and cannot be converted.
+DELETE FROM weather_cache;
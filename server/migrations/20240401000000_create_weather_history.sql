@@ -0,0 +1,15 @@
+-- Observed weather per provider, location and past day. Past days do not
+-- change, so rows are kept rather than refreshed.
+CREATE TABLE IF NOT EXISTS weather_history (
+    provider   TEXT    NOT NULL,
+    -- Coordinates scaled by 10^precision and rounded
+    latitude   INTEGER NOT NULL,
+    longitude  INTEGER NOT NULL,
+    -- YYYY-MM-DD, local to the location
+    date       TEXT    NOT NULL,
+    -- JSON-encoded HistoricalDay
+    payload    TEXT    NOT NULL,
+    -- Unix seconds
+    fetched_at INTEGER NOT NULL,
+    PRIMARY KEY (provider, latitude, longitude, date)
+);
@@ -0,0 +1,235 @@
+//! Sun and moon times computed locally, so they work for any coordinate
+//! and date without asking a provider.
+
+mod moon;
+mod sun;
+
+use crate::providers::dates;
+
+const SECS_PER_DAY: f64 = 86_400.0;
+/// 2000-01-01T12:00:00Z, the J2000.0 epoch
+const J2000: f64 = 946_728_000.0;
+
+/// Principal phases cover the day either side of the exact instant.
+const PRINCIPAL_PHASE_WINDOW: f64 = 1.0 / 29.53;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoonPhase {
+    NewMoon,
+    WaxingCrescent,
+    FirstQuarter,
+    WaxingGibbous,
+    FullMoon,
+    WaningGibbous,
+    LastQuarter,
+    WaningCrescent,
+}
+
+impl MoonPhase {
+    /// Phase at `age`, the fraction of the synodic month since new moon.
+    fn from_age(age: f64) -> Self {
+        let near = |target: f64| (age - target).abs() <= PRINCIPAL_PHASE_WINDOW;
+        if near(0.0) || near(1.0) {
+            MoonPhase::NewMoon
+        } else if near(0.25) {
+            MoonPhase::FirstQuarter
+        } else if near(0.5) {
+            MoonPhase::FullMoon
+        } else if near(0.75) {
+            MoonPhase::LastQuarter
+        } else if age < 0.25 {
+            MoonPhase::WaxingCrescent
+        } else if age < 0.5 {
+            MoonPhase::WaxingGibbous
+        } else if age < 0.75 {
+            MoonPhase::WaningGibbous
+        } else {
+            MoonPhase::WaningCrescent
+        }
+    }
+}
+
+/// Sun and moon events of one day at one place, in Unix seconds. An event
+/// that does not happen that day is `None`, e.g. sunset under the midnight
+/// sun or a moonrise skipped as the moon's rise time moves past midnight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Astronomy {
+    pub sunrise: Option<i64>,
+    pub sunset: Option<i64>,
+    pub solar_noon: i64,
+    pub civil_dawn: Option<i64>,
+    pub civil_dusk: Option<i64>,
+    pub nautical_dawn: Option<i64>,
+    pub nautical_dusk: Option<i64>,
+    /// Time between sunrise and sunset; a full day under the midnight sun
+    pub day_length_secs: i64,
+    pub moonrise: Option<i64>,
+    pub moonset: Option<i64>,
+    pub moon_phase: MoonPhase,
+    /// Fraction of the synodic month since new moon, 0 to 1 (0.5 is full)
+    pub moon_age: f64,
+    /// Illuminated fraction of the moon's disc, 0 to 1
+    pub moon_illumination: f64,
+}
+
+/// Events on `date` (`YYYY-MM-DD`) at the coordinate; `None` if the date
+/// does not parse. The day runs from local mean midnight to midnight, i.e.
+/// it follows the longitude rather than the civil timezone, which keeps
+/// every event on the right calendar day without a timezone database.
+pub fn compute(latitude: f64, longitude: f64, date: &str) -> Option<Astronomy> {
+    let (year, month, day) = dates::parse_date(date)?;
+    let midnight = dates::days_from_civil(year, month, day) as f64 * SECS_PER_DAY - longitude / 15.0 * 3600.0;
+    let mean_noon = midnight + SECS_PER_DAY / 2.0;
+
+    let event = |zenith: f64, rising: bool| sun::crossing(mean_noon, latitude, zenith, rising).ok().map(round);
+    let sunrise = event(sun::HORIZON_ZENITH, true);
+    let sunset = event(sun::HORIZON_ZENITH, false);
+    let day_length_secs = match (sunrise, sunset) {
+        (Some(rise), Some(set)) => set - rise,
+        _ => match sun::crossing(mean_noon, latitude, sun::HORIZON_ZENITH, true) {
+            Err(true) => SECS_PER_DAY as i64,
+            _ => 0,
+        },
+    };
+
+    let solar_noon = sun::solar_noon(mean_noon);
+    let (moonrise, moonset) = moon::rise_and_set(latitude, longitude, midnight, SECS_PER_DAY);
+    let (moon_age, moon_illumination) = lunation(solar_noon);
+
+    Some(Astronomy {
+        sunrise,
+        sunset,
+        solar_noon: round(solar_noon),
+        civil_dawn: event(sun::CIVIL_ZENITH, true),
+        civil_dusk: event(sun::CIVIL_ZENITH, false),
+        nautical_dawn: event(sun::NAUTICAL_ZENITH, true),
+        nautical_dusk: event(sun::NAUTICAL_ZENITH, false),
+        day_length_secs,
+        moonrise: moonrise.map(round),
+        moonset: moonset.map(round),
+        moon_phase: MoonPhase::from_age(moon_age),
+        moon_age,
+        moon_illumination,
+    })
+}
+
+/// Moon age and illuminated fraction at `timestamp`, from the angle
+/// between sun and moon as seen from the earth.
+fn lunation(timestamp: f64) -> (f64, f64) {
+    let sun = sun::position(timestamp);
+    let moon = moon::position(timestamp);
+
+    let difference = normalize_degrees(moon.longitude - sun.longitude);
+    let cos_elongation = moon.latitude.to_radians().cos() * difference.to_radians().cos();
+    (difference / 360.0, (1.0 - cos_elongation) / 2.0)
+}
+
+fn round(timestamp: f64) -> i64 {
+    timestamp.round() as i64
+}
+
+/// Days since J2000.0.
+fn julian_days(timestamp: f64) -> f64 {
+    (timestamp - J2000) / SECS_PER_DAY
+}
+
+/// Julian centuries since J2000.0.
+fn julian_centuries(timestamp: f64) -> f64 {
+    julian_days(timestamp) / 36_525.0
+}
+
+fn normalize_degrees(degrees: f64) -> f64 {
+    degrees.rem_euclid(360.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Unix seconds of a UTC time on 2024-03-20.
+    fn equinox_at(hour: i64, minute: i64) -> i64 {
+        1710892800 + hour * 3600 + minute * 60
+    }
+
+    fn assert_near(actual: Option<i64>, expected: i64, tolerance_secs: i64) {
+        let actual = actual.expect("event should happen");
+        assert!(
+            (actual - expected).abs() <= tolerance_secs,
+            "{} is more than {}s from {}",
+            actual,
+            tolerance_secs,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_london_equinox_sun_times() {
+        // Almanac values for London on 2024-03-20, in GMT
+        let day = compute(51.5074, -0.1278, "2024-03-20").unwrap();
+
+        assert_near(day.sunrise, equinox_at(6, 2), 120);
+        assert_near(day.sunset, equinox_at(18, 14), 120);
+        assert_near(Some(day.solar_noon), equinox_at(12, 8), 60);
+        assert_near(day.civil_dawn, equinox_at(5, 29), 120);
+        assert_near(day.civil_dusk, equinox_at(18, 47), 120);
+        assert_near(day.nautical_dawn, equinox_at(4, 50), 180);
+        assert_near(day.nautical_dusk, equinox_at(19, 26), 180);
+        assert!((day.day_length_secs - (12 * 3600 + 12 * 60)).abs() <= 180);
+    }
+
+    #[test]
+    fn test_events_stay_on_the_local_day() {
+        // Sydney is about ten hours ahead, so its sunrise falls on the previous UTC day
+        let day = compute(-33.8688, 151.2093, "2024-03-20").unwrap();
+
+        assert!(day.sunrise.unwrap() < equinox_at(0, 0));
+        assert_near(Some(day.solar_noon), equinox_at(2, 2), 120);
+        assert!(day.sunrise.unwrap() < day.solar_noon && day.solar_noon < day.sunset.unwrap());
+    }
+
+    #[test]
+    fn test_polar_day_and_night() {
+        let summer = compute(69.6492, 18.9553, "2024-06-21").unwrap();
+        assert_eq!(summer.sunrise, None);
+        assert_eq!(summer.sunset, None);
+        assert_eq!(summer.day_length_secs, 86_400);
+
+        let winter = compute(69.6492, 18.9553, "2024-12-21").unwrap();
+        assert_eq!(winter.sunrise, None);
+        assert_eq!(winter.day_length_secs, 0);
+        // The sun still gets close enough to the horizon for civil twilight
+        assert!(winter.civil_dawn.is_some());
+    }
+
+    #[test]
+    fn test_moon_phases() {
+        // Full moon on 2024-03-25 at 07:00 UTC
+        let full = compute(51.5074, -0.1278, "2024-03-25").unwrap();
+        assert_eq!(full.moon_phase, MoonPhase::FullMoon);
+        assert!(full.moon_illumination > 0.98);
+
+        // New moon (and total solar eclipse) on 2024-04-08 at 18:21 UTC
+        let new = compute(51.5074, -0.1278, "2024-04-08").unwrap();
+        assert_eq!(new.moon_phase, MoonPhase::NewMoon);
+        assert!(new.moon_illumination < 0.02);
+
+        let waxing = compute(51.5074, -0.1278, "2024-03-20").unwrap();
+        assert_eq!(waxing.moon_phase, MoonPhase::WaxingGibbous);
+        assert!(waxing.moon_age > 0.25 && waxing.moon_age < 0.5);
+    }
+
+    #[test]
+    fn test_full_moon_rises_around_sunset() {
+        let day = compute(51.5074, -0.1278, "2024-03-25").unwrap();
+
+        let moonrise = day.moonrise.expect("full moon rises in the evening");
+        assert!((moonrise - day.sunset.unwrap()).abs() < 90 * 60);
+        assert!(day.moonset.unwrap() < moonrise);
+    }
+
+    #[test]
+    fn test_invalid_date() {
+        assert!(compute(51.5, -0.12, "2024-02-31x").is_none());
+        assert!(compute(51.5, -0.12, "tomorrow").is_none());
+    }
+}
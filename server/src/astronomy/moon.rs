@@ -0,0 +1,106 @@
+//! Lunar position from the main periodic terms of Meeus, "Astronomical
+//! Algorithms" ch. 47: within a few tenths of a degree, which puts
+//! moonrise and moonset within a couple of minutes.
+
+use super::{julian_days, normalize_degrees};
+
+/// Mean obliquity of the ecliptic, close enough for the moon's coordinates.
+const OBLIQUITY: f64 = 23.4397;
+const EARTH_RADIUS_KM: f64 = 6378.14;
+
+pub(crate) struct LunarPosition {
+    /// Ecliptic longitude and latitude, degrees
+    pub longitude: f64,
+    pub latitude: f64,
+    pub distance_km: f64,
+}
+
+pub(crate) fn position(timestamp: f64) -> LunarPosition {
+    let d = julian_days(timestamp);
+
+    let mean_longitude = 218.316 + 13.176396 * d;
+    let elongation = (297.850 + 12.190749 * d).to_radians();
+    let sun_anomaly = (357.529 + 0.98560028 * d).to_radians();
+    let anomaly = (134.963 + 13.064993 * d).to_radians();
+    let argument = (93.272 + 13.229350 * d).to_radians();
+
+    let longitude = mean_longitude
+        + 6.289 * anomaly.sin()
+        + 1.274 * (2.0 * elongation - anomaly).sin()
+        + 0.658 * (2.0 * elongation).sin()
+        + 0.214 * (2.0 * anomaly).sin()
+        - 0.186 * sun_anomaly.sin()
+        - 0.114 * (2.0 * argument).sin();
+    let latitude = 5.128 * argument.sin()
+        + 0.281 * (anomaly + argument).sin()
+        + 0.278 * (anomaly - argument).sin()
+        + 0.173 * (2.0 * elongation - argument).sin();
+    let distance_km = 385_001.0
+        - 20_905.0 * anomaly.cos()
+        - 3_699.0 * (2.0 * elongation - anomaly).cos()
+        - 2_956.0 * (2.0 * elongation).cos();
+
+    LunarPosition {
+        longitude: normalize_degrees(longitude),
+        latitude,
+        distance_km,
+    }
+}
+
+/// Altitude of the moon's centre above the horizon, in degrees, minus the
+/// altitude at which it appears to rise or set.
+fn altitude_above_horizon(timestamp: f64, latitude: f64, longitude: f64) -> f64 {
+    let moon = position(timestamp);
+    let (lambda, beta) = (moon.longitude.to_radians(), moon.latitude.to_radians());
+    let epsilon = OBLIQUITY.to_radians();
+
+    let right_ascension = (lambda.sin() * epsilon.cos() - beta.tan() * epsilon.sin()).atan2(lambda.cos());
+    let declination = (beta.sin() * epsilon.cos() + beta.cos() * epsilon.sin() * lambda.sin()).asin();
+
+    let sidereal_time = 280.46061837 + 360.98564736629 * julian_days(timestamp) + longitude;
+    let hour_angle = sidereal_time.to_radians() - right_ascension;
+    let lat = latitude.to_radians();
+    let altitude = (lat.sin() * declination.sin() + lat.cos() * declination.cos() * hour_angle.cos())
+        .asin()
+        .to_degrees();
+
+    // Parallax lifts the apparent horizon; refraction and the radius lower it
+    let parallax = (EARTH_RADIUS_KM / moon.distance_km).asin().to_degrees();
+    altitude - (0.7275 * parallax - 0.5667)
+}
+
+/// First moonrise and moonset in `[start, start + duration)`, in Unix
+/// seconds; `None` for an event that does not happen in the window.
+pub(crate) fn rise_and_set(latitude: f64, longitude: f64, start: f64, duration: f64) -> (Option<f64>, Option<f64>) {
+    const STEP: f64 = 600.0;
+
+    let altitude = |t: f64| altitude_above_horizon(t, latitude, longitude);
+    let (mut rise, mut set) = (None, None);
+    let mut previous = (start, altitude(start));
+
+    while previous.0 < start + duration && (rise.is_none() || set.is_none()) {
+        let t = (previous.0 + STEP).min(start + duration);
+        let current = (t, altitude(t));
+        if previous.1 < 0.0 && current.1 >= 0.0 && rise.is_none() {
+            rise = Some(bisect(&altitude, previous.0, t));
+        } else if previous.1 >= 0.0 && current.1 < 0.0 && set.is_none() {
+            set = Some(bisect(&altitude, previous.0, t));
+        }
+        previous = current;
+    }
+    (rise, set)
+}
+
+/// Narrows a sign change of `f` between `low` and `high` to under a second.
+fn bisect(f: &impl Fn(f64) -> f64, mut low: f64, mut high: f64) -> f64 {
+    let rising = f(low) < 0.0;
+    while high - low > 1.0 {
+        let middle = (low + high) / 2.0;
+        if (f(middle) < 0.0) == rising {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    (low + high) / 2.0
+}
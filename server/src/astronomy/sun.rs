@@ -0,0 +1,91 @@
+//! Solar position after the NOAA solar calculator, good to about a minute
+//! for event times between 1900 and 2100.
+
+use super::{julian_centuries, normalize_degrees};
+
+/// Zenith angles of the sun's centre at each event. Sunrise and sunset
+/// account for refraction and the solar radius.
+pub(crate) const HORIZON_ZENITH: f64 = 90.833;
+pub(crate) const CIVIL_ZENITH: f64 = 96.0;
+pub(crate) const NAUTICAL_ZENITH: f64 = 102.0;
+
+/// Where the sun is at one instant, as needed for rise and set times.
+pub(crate) struct SolarPosition {
+    /// Declination in degrees
+    pub declination: f64,
+    /// Apparent minus mean solar time, in minutes
+    pub equation_of_time: f64,
+    /// Apparent ecliptic longitude in degrees
+    pub longitude: f64,
+}
+
+pub(crate) fn position(timestamp: f64) -> SolarPosition {
+    let t = julian_centuries(timestamp);
+
+    let mean_longitude = normalize_degrees(280.46646 + t * (36000.76983 + 0.0003032 * t));
+    let mean_anomaly = 357.52911 + t * (35999.05029 - 0.0001537 * t);
+    let eccentricity = 0.016708634 - t * (0.000042037 + 0.0000001267 * t);
+
+    let m = mean_anomaly.to_radians();
+    let center = m.sin() * (1.914602 - t * (0.004817 + 0.000014 * t))
+        + (2.0 * m).sin() * (0.019993 - 0.000101 * t)
+        + (3.0 * m).sin() * 0.000289;
+    let omega = (125.04 - 1934.136 * t).to_radians();
+    let apparent_longitude = mean_longitude + center - 0.00569 - 0.00478 * omega.sin();
+
+    let mean_obliquity = 23.0 + (26.0 + (21.448 - t * (46.815 + t * (0.00059 - t * 0.001813))) / 60.0) / 60.0;
+    let obliquity = (mean_obliquity + 0.00256 * omega.cos()).to_radians();
+    let declination = (obliquity.sin() * apparent_longitude.to_radians().sin()).asin().to_degrees();
+
+    let y = (obliquity / 2.0).tan().powi(2);
+    let l0 = mean_longitude.to_radians();
+    let equation_of_time = 4.0
+        * (y * (2.0 * l0).sin() - 2.0 * eccentricity * m.sin()
+            + 4.0 * eccentricity * y * m.sin() * (2.0 * l0).cos()
+            - 0.5 * y * y * (4.0 * l0).sin()
+            - 1.25 * eccentricity * eccentricity * (2.0 * m).sin())
+        .to_degrees();
+
+    SolarPosition {
+        declination,
+        equation_of_time,
+        longitude: normalize_degrees(apparent_longitude),
+    }
+}
+
+/// Unix seconds of solar noon on the solar day centred on `mean_noon`, the
+/// instant of local mean noon.
+pub(crate) fn solar_noon(mean_noon: f64) -> f64 {
+    let first = mean_noon - position(mean_noon).equation_of_time * 60.0;
+    mean_noon - position(first).equation_of_time * 60.0
+}
+
+/// When the sun's centre crosses `zenith` degrees before (`rising`) or after
+/// solar noon. `Err(true)` if it stays above all day, `Err(false)` if it
+/// stays below.
+pub(crate) fn crossing(mean_noon: f64, latitude: f64, zenith: f64, rising: bool) -> Result<f64, bool> {
+    let direction = if rising { -1.0 } else { 1.0 };
+    let mut estimate = solar_noon(mean_noon);
+
+    // The sun moves during the day, so refine against its position then
+    for _ in 0..3 {
+        let sun = position(estimate);
+        let hour_angle = hour_angle(latitude, sun.declination, zenith)?;
+        let noon = mean_noon - sun.equation_of_time * 60.0;
+        estimate = noon + direction * hour_angle * 240.0;
+    }
+    Ok(estimate)
+}
+
+/// Hour angle in degrees at which the sun reaches `zenith`.
+fn hour_angle(latitude: f64, declination: f64, zenith: f64) -> Result<f64, bool> {
+    let (lat, dec) = (latitude.to_radians(), declination.to_radians());
+    let cos_h = (zenith.to_radians().cos() - lat.sin() * dec.sin()) / (lat.cos() * dec.cos());
+    if cos_h < -1.0 {
+        Err(true)
+    } else if cos_h > 1.0 {
+        Err(false)
+    } else {
+        Ok(cos_h.acos().to_degrees())
+    }
+}
@@ -6,11 +6,166 @@ use std::env;
 pub struct ProvidersConfig {
     pub openweather_api_key: String,
     pub weatherapi_api_key: String,
+    /// Ids of the providers that may serve requests. Providers left out are
+    /// still registered but reported as disabled.
+    #[serde(default = "default_enabled_providers")]
+    pub enabled: Vec<String>,
+    /// Provider ids tried in order when no provider is requested or the
+    /// requested one fails.
+    #[serde(default = "default_enabled_providers")]
+    pub fallback_chain: Vec<String>,
+    /// How long a single provider call may take before the next one is tried.
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_enabled_providers() -> Vec<String> {
+    vec![
+        "openweather".to_string(),
+        "weatherapi".to_string(),
+        "openmeteo".to_string(),
+    ]
+}
+
+fn default_timeout_ms() -> u64 {
+    10_000
+}
+
+/// In-memory cache in front of every provider.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    /// Decimal places coordinates are rounded to when building cache keys.
+    pub precision: u32,
+    pub current_ttl_secs: u64,
+    pub forecast_ttl_secs: u64,
+    /// Upper bound on cached entries per kind before LRU eviction.
+    pub max_entries: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            precision: 2,
+            current_ttl_secs: 300,
+            forecast_ttl_secs: 1800,
+            max_entries: 1000,
+        }
+    }
+}
+
+/// SQLite persistence of provider responses across restarts.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    pub enabled: bool,
+    pub database_url: String,
+    /// Decimal places coordinates are rounded to when storing responses.
+    pub precision: u32,
+    /// Stored current weather younger than this is served without calling
+    /// the provider. Capped at `cache.current_ttl_secs` when the cache is on.
+    pub current_fresh_ttl_secs: u64,
+    /// Likewise for forecasts, capped at `cache.forecast_ttl_secs`.
+    pub forecast_fresh_ttl_secs: u64,
+    /// Oldest stored response still served (marked stale) when every provider fails.
+    pub max_stale_secs: u64,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            database_url: "sqlite://weather.db".to_string(),
+            precision: 2,
+            current_fresh_ttl_secs: 300,
+            forecast_fresh_ttl_secs: 1800,
+            max_stale_secs: 86_400,
+        }
+    }
+}
+
+impl StorageConfig {
+    /// These settings with each fresh TTL cut to the matching cache TTL, so
+    /// a cache miss is never answered with older data than the cache holds.
+    pub fn capped_by(&self, cache: &CacheConfig) -> Self {
+        let mut capped = self.clone();
+        if cache.enabled {
+            capped.current_fresh_ttl_secs = capped.current_fresh_ttl_secs.min(cache.current_ttl_secs);
+            capped.forecast_fresh_ttl_secs = capped.forecast_fresh_ttl_secs.min(cache.forecast_ttl_secs);
+        }
+        capped
+    }
+}
+
+/// Background polling behind WatchWeather subscriptions.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WatchConfig {
+    /// How often each watched location is refreshed from its provider.
+    pub poll_interval_secs: u64,
+    /// Decimal places coordinates are rounded to, so nearby subscriptions share a poller.
+    pub precision: u32,
+    /// Most locations a single subscription may watch.
+    pub max_locations: usize,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_secs: 300,
+            precision: 2,
+            max_locations: 20,
+        }
+    }
+}
+
+/// Server-side place search and reverse geocoding.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct GeocodingConfig {
+    /// Geocoder ids tried in order; the API keys come from `[providers]`.
+    /// `gazetteer` needs no network.
+    pub geocoders: Vec<String>,
+    /// How long search and reverse lookup results are cached; 0 disables the cache.
+    pub cache_ttl_secs: u64,
+    /// Upper bound on cached lookups per kind and geocoder before LRU eviction.
+    pub max_entries: usize,
+    /// Decimal places coordinates are rounded to when caching reverse lookups.
+    pub precision: u32,
+    /// GeoNames cities dump loaded by the `gazetteer` geocoder; empty for
+    /// the major cities bundled with the server.
+    pub gazetteer_path: String,
+}
+
+impl Default for GeocodingConfig {
+    fn default() -> Self {
+        Self {
+            geocoders: vec![
+                "openweather".to_string(),
+                "weatherapi".to_string(),
+                "gazetteer".to_string(),
+            ],
+            cache_ttl_secs: 86_400,
+            max_entries: 1000,
+            precision: 2,
+            gazetteer_path: String::new(),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Settings {
     pub providers: ProvidersConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub watch: WatchConfig,
+    #[serde(default)]
+    pub geocoding: GeocodingConfig,
 }
 
 impl Settings {
@@ -31,4 +186,4 @@ impl Settings {
         // Deserialize the config into our Settings struct
         s.try_deserialize()
     }
-}
\ No newline at end of file
+}
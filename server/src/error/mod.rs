@@ -18,6 +18,24 @@ pub enum AppError {
 
     #[error("Not found: {0}")]
     NotFound(String),
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(error: sqlx::Error) -> Self {
+        AppError::Storage(error.to_string())
+    }
+}
+
+impl From<sqlx::migrate::MigrateError> for AppError {
+    fn from(error: sqlx::migrate::MigrateError) -> Self {
+        AppError::Storage(error.to_string())
+    }
 }
 
 impl From<AppError> for Status {
@@ -28,6 +46,8 @@ impl From<AppError> for Status {
             AppError::WeatherApi(e) => Status::unavailable(e),
             AppError::Invalidreqwest(e) => Status::invalid_argument(e),
             AppError::NotFound(e) => Status::not_found(e),
+            AppError::Storage(e) => Status::internal(format!("Storage error: {}", e)),
+            AppError::Unsupported(e) => Status::unimplemented(e),
         }
     }
 }
@@ -77,4 +97,20 @@ mod tests {
         assert_eq!(status.code(), tonic::Code::NotFound);
         assert_eq!(status.message(), "resource not found");
     }
+
+    #[test]
+    fn test_storage_error_conversion() {
+        let error = AppError::Storage("database is locked".to_string());
+        let status = Status::from(error);
+        assert_eq!(status.code(), tonic::Code::Internal);
+        assert_eq!(status.message(), "Storage error: database is locked");
+    }
+
+    #[test]
+    fn test_unsupported_error_conversion() {
+        let error = AppError::Unsupported("no alerts here".to_string());
+        let status = Status::from(error);
+        assert_eq!(status.code(), tonic::Code::Unimplemented);
+        assert_eq!(status.message(), "no alerts here");
+    }
 }
\ No newline at end of file
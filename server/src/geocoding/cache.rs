@@ -0,0 +1,128 @@
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use crate::config::GeocodingConfig;
+use crate::geocoding::{Geocoder, Locality, Place};
+use crate::providers::ttl::TtlLru;
+use tracing::debug;
+
+/// TTL + LRU cache wrapped around another geocoder. Place names change
+/// rarely, so entries can live far longer than weather. Errors are never
+/// cached; "no place here" is.
+pub struct CachedGeocoder {
+    inner: Arc<dyn Geocoder>,
+    ttl: Duration,
+    scale: f64,
+    searches: TtlLru<(String, usize), Vec<Place>>,
+    localities: TtlLru<(i64, i64), Option<Locality>>,
+}
+
+impl CachedGeocoder {
+    pub fn new(inner: Arc<dyn Geocoder>, config: &GeocodingConfig) -> Self {
+        Self {
+            inner,
+            ttl: Duration::from_secs(config.cache_ttl_secs),
+            scale: 10f64.powi(config.precision as i32),
+            searches: TtlLru::new(config.max_entries),
+            localities: TtlLru::new(config.max_entries),
+        }
+    }
+}
+
+#[async_trait]
+impl Geocoder for CachedGeocoder {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<Place>, Box<dyn std::error::Error + Send + Sync>> {
+        // "London " and "london" are the same search
+        let key = (query.trim().to_lowercase(), limit);
+        if let Some(places) = self.searches.get(&key) {
+            debug!(geocoder = %self.inner.name(), ?key, "Search cache hit");
+            return Ok(places);
+        }
+        debug!(geocoder = %self.inner.name(), ?key, "Search cache miss");
+
+        let places = self.inner.search(query, limit).await?;
+        self.searches.insert(key, places.clone(), self.ttl);
+        Ok(places)
+    }
+
+    async fn reverse(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Option<Locality>, Box<dyn std::error::Error + Send + Sync>> {
+        let key = ((latitude * self.scale).round() as i64, (longitude * self.scale).round() as i64);
+        if let Some(locality) = self.localities.get(&key) {
+            debug!(geocoder = %self.inner.name(), ?key, "Reverse lookup cache hit");
+            return Ok(locality);
+        }
+        debug!(geocoder = %self.inner.name(), ?key, "Reverse lookup cache miss");
+
+        let locality = self.inner.reverse(latitude, longitude).await?;
+        self.localities.insert(key, locality.clone(), self.ttl);
+        Ok(locality)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::support::StubGeocoder;
+
+    fn config() -> GeocodingConfig {
+        GeocodingConfig {
+            cache_ttl_secs: 60,
+            max_entries: 10,
+            precision: 2,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_repeated_search_served_from_cache_until_expiry() {
+        let stub = Arc::new(StubGeocoder::new("Stub"));
+        let cached = CachedGeocoder::new(stub.clone(), &config());
+
+        cached.search("London", 5).await.unwrap();
+        cached.search(" london", 5).await.unwrap();
+        assert_eq!(stub.calls(), 1);
+
+        // A different limit is a different answer
+        cached.search("london", 1).await.unwrap();
+        assert_eq!(stub.calls(), 2);
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+        cached.search("london", 5).await.unwrap();
+        assert_eq!(stub.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_nearby_reverse_lookups_share_an_entry() {
+        let stub = Arc::new(StubGeocoder::new("Stub").without_places());
+        let cached = CachedGeocoder::new(stub.clone(), &config());
+
+        assert_eq!(cached.reverse(10.001, 20.001).await.unwrap(), None);
+        assert_eq!(cached.reverse(10.004, 19.998).await.unwrap(), None);
+        assert_eq!(stub.calls(), 1);
+
+        cached.reverse(10.02, 20.0).await.unwrap();
+        assert_eq!(stub.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_not_cached() {
+        let stub = Arc::new(StubGeocoder::new("Stub").failing());
+        let cached = CachedGeocoder::new(stub.clone(), &config());
+
+        assert!(cached.search("london", 5).await.is_err());
+        assert!(cached.search("london", 5).await.is_err());
+        assert_eq!(stub.calls(), 2);
+    }
+}
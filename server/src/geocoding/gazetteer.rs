@@ -0,0 +1,347 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use async_trait::async_trait;
+use crate::error::{AppError, AppResult};
+use crate::geocoding::{Geocoder, Locality, Place};
+use tracing::info;
+
+/// Major world cities compiled into the binary, so lookups work with no
+/// network and no data files.
+const BUNDLED_CITIES: &str = include_str!("../../data/cities.tsv");
+
+/// Reverse lookups farther than this from every known city find nothing.
+const MAX_REVERSE_DISTANCE_KM: f64 = 50.0;
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Columns of the GeoNames `cities*.txt` dumps that the index uses, see
+/// https://download.geonames.org/export/dump/readme.txt
+const NAME: usize = 1;
+const ASCII_NAME: usize = 2;
+const ALTERNATE_NAMES: usize = 3;
+const LATITUDE: usize = 4;
+const LONGITUDE: usize = 5;
+const COUNTRY_CODE: usize = 8;
+const POPULATION: usize = 14;
+const TIMEZONE: usize = 17;
+
+#[derive(Debug)]
+struct City {
+    name: String,
+    /// ISO 3166 alpha-2 code
+    country: String,
+    latitude: f64,
+    longitude: f64,
+    population: u64,
+    timezone: String,
+    /// Lowercased ASCII name, for typo-tolerant matching.
+    folded: String,
+}
+
+/// How well a city matched a search; lower ranks first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Match {
+    Exact,
+    Prefix,
+    /// Within this many edits of the query.
+    Fuzzy(usize),
+}
+
+/// Offline `Geocoder` over a GeoNames-style cities table held in memory.
+/// Names and alternate names are indexed for prefix search; misspelled
+/// queries fall back to edit distance over the ASCII names. Ties go to the
+/// most populous city, and regions stay unset since GeoNames only gives
+/// admin codes.
+pub struct Gazetteer {
+    /// Sorted by population, largest first.
+    cities: Vec<City>,
+    /// Lowercased name or alternate name to indices into `cities`.
+    names: BTreeMap<String, Vec<usize>>,
+}
+
+impl Gazetteer {
+    /// The cities bundled with the server.
+    pub fn bundled() -> AppResult<Self> {
+        Self::parse(BUNDLED_CITIES)
+    }
+
+    /// A GeoNames dump such as `cities15000.txt`, read once at startup.
+    pub fn from_path(path: impl AsRef<Path>) -> AppResult<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| AppError::Config(format!("Failed to read gazetteer {}: {}", path.display(), e)))?;
+        let gazetteer = Self::parse(&contents)?;
+        info!(path = %path.display(), cities = gazetteer.cities.len(), "Loaded gazetteer");
+        Ok(gazetteer)
+    }
+
+    fn parse(contents: &str) -> AppResult<Self> {
+        let mut rows = Vec::new();
+        for (number, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |what: &str| AppError::Config(format!("Gazetteer line {}: {}", number + 1, what));
+            let columns: Vec<&str> = line.split('\t').collect();
+            if columns.len() <= TIMEZONE {
+                return Err(invalid("too few columns"));
+            }
+            let coordinate = |column: usize| columns[column].parse::<f64>().map_err(|_| invalid("invalid coordinate"));
+
+            let city = City {
+                name: columns[NAME].to_string(),
+                country: columns[COUNTRY_CODE].to_string(),
+                latitude: coordinate(LATITUDE)?,
+                longitude: coordinate(LONGITUDE)?,
+                population: columns[POPULATION].parse().unwrap_or(0),
+                timezone: columns[TIMEZONE].to_string(),
+                folded: columns[ASCII_NAME].to_lowercase(),
+            };
+            rows.push((city, columns[ALTERNATE_NAMES]));
+        }
+
+        // Population order lets ranking fall back on plain index order
+        rows.sort_by_key(|(city, _)| Reverse(city.population));
+        let mut cities = Vec::with_capacity(rows.len());
+        let mut names: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (index, (city, alternates)) in rows.into_iter().enumerate() {
+            let keys: HashSet<String> = [city.name.as_str(), city.folded.as_str()]
+                .into_iter()
+                .chain(alternates.split(','))
+                .map(|name| name.trim().to_lowercase())
+                .filter(|name| !name.is_empty())
+                .collect();
+            for key in keys {
+                names.entry(key).or_default().push(index);
+            }
+            cities.push(city);
+        }
+
+        Ok(Self { cities, names })
+    }
+
+    fn matches(&self, name: &str, country: Option<&str>, limit: usize) -> Vec<usize> {
+        let in_country = |index: &usize| {
+            country.is_none_or(|country| self.cities[*index].country.eq_ignore_ascii_case(country))
+        };
+
+        let mut found: Vec<(Match, usize)> = Vec::new();
+        for (key, indices) in self.names.range(name.to_string()..) {
+            if !key.starts_with(name) {
+                break;
+            }
+            let kind = if key == name { Match::Exact } else { Match::Prefix };
+            found.extend(indices.iter().filter(|i| in_country(i)).map(|&i| (kind, i)));
+        }
+
+        // Only spend edit distance on queries that found too little otherwise
+        let allowed = max_edits(name);
+        if found.len() < limit && allowed > 0 {
+            for (index, city) in self.cities.iter().enumerate() {
+                if !in_country(&index) || city.folded.chars().count().abs_diff(name.chars().count()) > allowed {
+                    continue;
+                }
+                let edits = edit_distance(name, &city.folded);
+                if (1..=allowed).contains(&edits) {
+                    found.push((Match::Fuzzy(edits), index));
+                }
+            }
+        }
+
+        // Indices follow population, so this ranks by match, then by size
+        found.sort();
+        let mut seen = HashSet::new();
+        found
+            .into_iter()
+            .map(|(_, index)| index)
+            .filter(|index| seen.insert(*index))
+            .take(limit)
+            .collect()
+    }
+
+    fn nearest(&self, latitude: f64, longitude: f64) -> Option<&City> {
+        self.cities
+            .iter()
+            .map(|city| (distance_km(latitude, longitude, city.latitude, city.longitude), city))
+            .filter(|(distance, _)| *distance <= MAX_REVERSE_DISTANCE_KM)
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, city)| city)
+    }
+}
+
+/// Splits "London, GB" into the name and an optional trailing country code.
+fn parse_query(query: &str) -> (String, Option<&str>) {
+    match query.rsplit_once(',') {
+        Some((name, country)) if country.trim().len() == 2 => (name.trim().to_lowercase(), Some(country.trim())),
+        _ => (query.trim().to_lowercase(), None),
+    }
+}
+
+/// Typos tolerated for a query: none for short ones, which would match
+/// too much.
+fn max_edits(query: &str) -> usize {
+    match query.chars().count() {
+        0..=4 => 0,
+        5..=8 => 1,
+        _ => 2,
+    }
+}
+
+/// Optimal string alignment distance: insertions, deletions, substitutions
+/// and transpositions of adjacent characters each cost one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Great-circle distance by the haversine formula.
+fn distance_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+    let h = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+#[async_trait]
+impl Geocoder for Gazetteer {
+    fn name(&self) -> String {
+        "Gazetteer".to_string()
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<Place>, Box<dyn std::error::Error + Send + Sync>> {
+        let (name, country) = parse_query(query);
+        Ok(self
+            .matches(&name, country, limit)
+            .into_iter()
+            .map(|index| {
+                let city = &self.cities[index];
+                Place {
+                    name: city.name.clone(),
+                    state: None,
+                    country: city.country.clone(),
+                    latitude: city.latitude,
+                    longitude: city.longitude,
+                }
+            })
+            .collect())
+    }
+
+    async fn reverse(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Option<Locality>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.nearest(latitude, longitude).map(|city| Locality {
+            name: city.name.clone(),
+            region: None,
+            country: city.country.clone(),
+            timezone: Some(city.timezone.clone()).filter(|tz| !tz.is_empty()),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn search(query: &str, limit: usize) -> Vec<Place> {
+        Gazetteer::bundled().unwrap().search(query, limit).await.unwrap()
+    }
+
+    #[test]
+    fn test_bundled_cities_load() {
+        let gazetteer = Gazetteer::bundled().unwrap();
+        assert!(gazetteer.cities.len() > 50);
+        assert!(gazetteer.cities.windows(2).all(|w| w[0].population >= w[1].population));
+    }
+
+    #[tokio::test]
+    async fn test_prefix_search_ranks_by_population() {
+        let places = search("Lond", 5).await;
+        let countries: Vec<&str> = places.iter().map(|p| p.country.as_str()).collect();
+        assert_eq!(countries, ["GB", "CA"]);
+
+        // An exact name beats a more populous prefix match
+        let places = search("paris", 5).await;
+        assert_eq!(places[0].country, "FR");
+        assert_eq!(places[1].country, "US");
+    }
+
+    #[tokio::test]
+    async fn test_search_by_alternate_name_and_country() {
+        assert_eq!(search("München", 1).await[0].name, "Munich");
+        assert_eq!(search("Bombay", 1).await[0].name, "Mumbai");
+
+        let places = search("London, ca", 5).await;
+        assert_eq!(places.len(), 1);
+        assert_eq!(places[0].latitude, 42.98339);
+    }
+
+    #[tokio::test]
+    async fn test_fuzzy_search_tolerates_typos() {
+        assert_eq!(search("Lodnon", 1).await[0].name, "London");
+        assert_eq!(search("Amsterdma", 1).await[0].name, "Amsterdam");
+        assert!(search("Xqzv", 5).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reverse_finds_nearest_city() {
+        let gazetteer = Gazetteer::bundled().unwrap();
+
+        // Westminster, a few km from the centre of London
+        let locality = gazetteer.reverse(51.4975, -0.1357).await.unwrap().unwrap();
+        assert_eq!(locality.name, "London");
+        assert_eq!(locality.country, "GB");
+        assert_eq!(locality.timezone.as_deref(), Some("Europe/London"));
+
+        // Mid-Atlantic
+        assert_eq!(gazetteer.reverse(0.0, -30.0).await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_malformed_rows_are_rejected() {
+        let error = Gazetteer::parse("# header\n1\tShort\trow\n").err().unwrap();
+        assert!(matches!(error, AppError::Config(message) if message.contains("line 2")));
+
+        let row = "1\tNowhere\tNowhere\t\tnorth\t0\tP\tPPL\tXX\t\t\t\t\t\t0\t\t0\tUTC\t2024-01-01";
+        assert!(Gazetteer::parse(row).is_err());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("london", "london"), 0);
+        assert_eq!(edit_distance("lodnon", "london"), 1);
+        assert_eq!(edit_distance("berln", "berlin"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_distance_km() {
+        // London to Paris
+        let distance = distance_km(51.50853, -0.12574, 48.85341, 2.3488);
+        assert!((distance - 343.5).abs() < 2.0);
+    }
+}
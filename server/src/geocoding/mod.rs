@@ -0,0 +1,255 @@
+//! Place search and reverse geocoding behind a `Geocoder` trait, so
+//! third-party API keys stay on the server, lookups can be cached, and an
+//! offline gazetteer can answer without any network.
+
+mod cache;
+mod gazetteer;
+mod openweather;
+mod weatherapi;
+
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use crate::config::{GeocodingConfig, ProvidersConfig};
+use crate::error::{AppError, AppResult};
+use tracing::{debug, warn};
+
+pub use cache::CachedGeocoder;
+pub use gazetteer::Gazetteer;
+pub use openweather::OpenWeatherGeocoder;
+pub use weatherapi::WeatherApiGeocoder;
+
+/// A named place matching a search.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Place {
+    pub name: String,
+    /// State, province or region, when the geocoder knows one.
+    pub state: Option<String>,
+    /// Country as the geocoder reports it: an ISO 3166 code for
+    /// OpenWeather, the English name for WeatherAPI.
+    pub country: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// The named place a coordinate lies in or nearest to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Locality {
+    /// City, town or village.
+    pub name: String,
+    /// State, province or region, when known.
+    pub region: Option<String>,
+    pub country: String,
+    /// IANA time zone id such as `Europe/London`, when known.
+    pub timezone: Option<String>,
+}
+
+#[async_trait]
+pub trait Geocoder: Send + Sync {
+    fn name(&self) -> String;
+
+    /// Up to `limit` places matching `query`, best match first.
+    async fn search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<Place>, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// The locality at a coordinate, or `None` where the geocoder knows of
+    /// none, e.g. out at sea.
+    async fn reverse(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Option<Locality>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// A geocoder's answer, with the name of the geocoder that gave it.
+#[derive(Debug)]
+pub struct Answer<T> {
+    pub value: T,
+    pub geocoder: String,
+}
+
+/// Geocoders tried in order until one answers within the timeout.
+#[derive(Default)]
+pub struct GeocoderChain {
+    geocoders: Vec<Arc<dyn Geocoder>>,
+    timeout: Duration,
+}
+
+impl GeocoderChain {
+    pub fn new(geocoders: Vec<Arc<dyn Geocoder>>, timeout: Duration) -> Self {
+        Self { geocoders, timeout }
+    }
+
+    /// Builds the geocoders listed in `geocoding.geocoders`, the online ones
+    /// behind a cache when `cache_ttl_secs` is set. The gazetteer is loaded
+    /// here, so a bad data file fails startup.
+    pub fn from_settings(providers: &ProvidersConfig, config: &GeocodingConfig) -> AppResult<Self> {
+        let mut geocoders: Vec<Arc<dyn Geocoder>> = Vec::new();
+        for id in &config.geocoders {
+            let geocoder: Arc<dyn Geocoder> = match id.to_ascii_lowercase().as_str() {
+                // Already in memory, so there is nothing to cache
+                "gazetteer" => {
+                    let gazetteer = if config.gazetteer_path.is_empty() {
+                        Gazetteer::bundled()?
+                    } else {
+                        Gazetteer::from_path(&config.gazetteer_path)?
+                    };
+                    geocoders.push(Arc::new(gazetteer));
+                    continue;
+                }
+                "openweather" => Arc::new(OpenWeatherGeocoder::new(providers.openweather_api_key.clone())),
+                "weatherapi" => Arc::new(WeatherApiGeocoder::new(providers.weatherapi_api_key.clone())),
+                _ => return Err(AppError::Config(format!("Unknown geocoder in geocoding.geocoders: {}", id))),
+            };
+            geocoders.push(if config.cache_ttl_secs > 0 {
+                Arc::new(CachedGeocoder::new(geocoder, config))
+            } else {
+                geocoder
+            });
+        }
+        Ok(Self::new(geocoders, Duration::from_millis(providers.timeout_ms)))
+    }
+
+    pub async fn search(&self, query: &str, limit: usize) -> AppResult<Answer<Vec<Place>>> {
+        if self.geocoders.is_empty() {
+            return Err(AppError::Unsupported("No geocoder is configured".to_string()));
+        }
+
+        let mut failures = Vec::new();
+        for geocoder in &self.geocoders {
+            let name = geocoder.name();
+            match tokio::time::timeout(self.timeout, geocoder.search(query, limit)).await {
+                Ok(Ok(places)) => {
+                    debug!(geocoder = %name, places = places.len(), "Geocoder answered search");
+                    return Ok(Answer { value: places, geocoder: name });
+                }
+                Ok(Err(e)) => {
+                    warn!(geocoder = %name, error = %e, "Geocoder failed, trying next");
+                    failures.push(format!("{}: {}", name, e));
+                }
+                Err(_) => {
+                    warn!(geocoder = %name, timeout = ?self.timeout, "Geocoder timed out, trying next");
+                    failures.push(format!("{}: timed out after {:?}", name, self.timeout));
+                }
+            }
+        }
+
+        Err(AppError::WeatherApi(format!("All geocoders failed: {}", failures.join("; "))))
+    }
+
+    /// The first locality any geocoder knows at this coordinate. Fails with
+    /// `NotFound` when every geocoder answered but none knew a place.
+    pub async fn reverse(&self, latitude: f64, longitude: f64) -> AppResult<Answer<Locality>> {
+        if self.geocoders.is_empty() {
+            return Err(AppError::Unsupported("No geocoder is configured".to_string()));
+        }
+
+        let mut failures = Vec::new();
+        for geocoder in &self.geocoders {
+            let name = geocoder.name();
+            match tokio::time::timeout(self.timeout, geocoder.reverse(latitude, longitude)).await {
+                Ok(Ok(Some(locality))) => {
+                    debug!(geocoder = %name, locality = %locality.name, "Geocoder answered reverse lookup");
+                    return Ok(Answer { value: locality, geocoder: name });
+                }
+                Ok(Ok(None)) => debug!(geocoder = %name, "Geocoder knows no place here, trying next"),
+                Ok(Err(e)) => {
+                    warn!(geocoder = %name, error = %e, "Geocoder failed, trying next");
+                    failures.push(format!("{}: {}", name, e));
+                }
+                Err(_) => {
+                    warn!(geocoder = %name, timeout = ?self.timeout, "Geocoder timed out, trying next");
+                    failures.push(format!("{}: timed out after {:?}", name, self.timeout));
+                }
+            }
+        }
+
+        if failures.is_empty() {
+            Err(AppError::NotFound(format!("No place known at {}, {}", latitude, longitude)))
+        } else {
+            Err(AppError::WeatherApi(format!("All geocoders failed: {}", failures.join("; "))))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::support::StubGeocoder;
+
+    fn providers() -> ProvidersConfig {
+        ProvidersConfig {
+            openweather_api_key: "test_key".to_string(),
+            weatherapi_api_key: "test_key".to_string(),
+            enabled: Vec::new(),
+            fallback_chain: Vec::new(),
+            timeout_ms: 1000,
+        }
+    }
+
+    fn chain(geocoders: Vec<StubGeocoder>) -> GeocoderChain {
+        let geocoders = geocoders.into_iter().map(|g| Arc::new(g) as Arc<dyn Geocoder>).collect();
+        GeocoderChain::new(geocoders, Duration::from_millis(50))
+    }
+
+    #[tokio::test]
+    async fn test_search_falls_back_to_next_geocoder() {
+        let chain = chain(vec![StubGeocoder::new("First").failing(), StubGeocoder::new("Second")]);
+
+        let answer = chain.search("london", 5).await.unwrap();
+        assert_eq!(answer.geocoder, "Second");
+        assert_eq!(answer.value[0].name, "London");
+    }
+
+    #[tokio::test]
+    async fn test_search_without_geocoders_is_unsupported() {
+        let error = chain(Vec::new()).search("london", 5).await.unwrap_err();
+        assert!(matches!(error, AppError::Unsupported(_)));
+
+        let error = chain(vec![StubGeocoder::new("Only").failing()]).search("london", 5).await.unwrap_err();
+        assert!(matches!(error, AppError::WeatherApi(_)));
+    }
+
+    #[tokio::test]
+    async fn test_reverse_skips_geocoders_without_a_place() {
+        let answer = chain(vec![StubGeocoder::new("Sea").without_places(), StubGeocoder::new("Land")])
+            .reverse(51.5, -0.12)
+            .await
+            .unwrap();
+        assert_eq!(answer.geocoder, "Land");
+        assert_eq!(answer.value.timezone.as_deref(), Some("Europe/London"));
+
+        let error = chain(vec![StubGeocoder::new("Sea").without_places()]).reverse(0.0, -30.0).await.unwrap_err();
+        assert!(matches!(error, AppError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_gazetteer_from_settings_works_offline() {
+        let config = GeocodingConfig {
+            geocoders: vec!["Gazetteer".to_string()],
+            ..Default::default()
+        };
+        let chain = GeocoderChain::from_settings(&providers(), &config).unwrap();
+
+        let answer = chain.search("berlin", 1).await.unwrap();
+        assert_eq!(answer.geocoder, "Gazetteer");
+        assert_eq!(answer.value[0].country, "DE");
+
+        let config = GeocodingConfig {
+            geocoders: vec!["gazetteer".to_string()],
+            gazetteer_path: "/nonexistent/cities15000.txt".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(GeocoderChain::from_settings(&providers(), &config), Err(AppError::Config(_))));
+
+        let config = GeocodingConfig {
+            geocoders: vec!["atlas".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(GeocoderChain::from_settings(&providers(), &config), Err(AppError::Config(_))));
+    }
+}
@@ -0,0 +1,194 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use crate::geocoding::{Geocoder, Locality, Place};
+use reqwest;
+use tracing::debug;
+
+const OPENWEATHER_BASE_URL: &str = "https://api.openweathermap.org";
+
+/// OpenWeather's Geocoding API, which shares the weather API key.
+pub struct OpenWeatherGeocoder {
+    api_key: String,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl OpenWeatherGeocoder {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self::with_base_url(api_key, OPENWEATHER_BASE_URL)
+    }
+
+    pub fn with_base_url(api_key: impl Into<String>, base_url: &str) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct DirectMatch {
+    name: String,
+    lat: f64,
+    lon: f64,
+    /// ISO 3166 alpha-2 code
+    country: String,
+    state: Option<String>,
+}
+
+#[async_trait]
+impl Geocoder for OpenWeatherGeocoder {
+    fn name(&self) -> String {
+        "OpenWeather".to_string()
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<Place>, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!("{}/geo/1.0/direct", self.base_url);
+        debug!(query, limit, "Searching places on OpenWeather");
+
+        let matches: Vec<DirectMatch> = self.client
+            .get(&url)
+            .query(&[("q", query), ("limit", &limit.to_string()), ("appid", &self.api_key)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(matches
+            .into_iter()
+            .take(limit)
+            .map(|m| Place {
+                name: m.name,
+                state: m.state,
+                country: m.country,
+                latitude: m.lat,
+                longitude: m.lon,
+            })
+            .collect())
+    }
+
+    /// OpenWeather knows no time zone ids, so `timezone` stays unset.
+    async fn reverse(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Option<Locality>, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!("{}/geo/1.0/reverse", self.base_url);
+        debug!(latitude, longitude, "Reverse geocoding on OpenWeather");
+
+        let matches: Vec<DirectMatch> = self.client
+            .get(&url)
+            .query(&[
+                ("lat", latitude.to_string()),
+                ("lon", longitude.to_string()),
+                ("limit", "1".to_string()),
+                ("appid", self.api_key.clone()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(matches.into_iter().next().map(|m| Locality {
+            name: m.name,
+            region: m.state,
+            country: m.country,
+            timezone: None,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIRECT_FIXTURE: &str = include_str!("../tests/fixtures/openweather_geocoding.json");
+    const REVERSE_FIXTURE: &str = include_str!("../tests/fixtures/openweather_reverse.json");
+
+    #[tokio::test]
+    async fn test_search_from_fixture() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/geo/1.0/direct")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("q".into(), "London, GB".into()),
+                mockito::Matcher::UrlEncoded("limit".into(), "2".into()),
+            ]))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(DIRECT_FIXTURE)
+            .create_async()
+            .await;
+        let geocoder = OpenWeatherGeocoder::with_base_url("test_key", &server.url());
+
+        let places = geocoder.search("London, GB", 2).await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(places.len(), 2);
+        assert_eq!(places[0].name, "London");
+        assert_eq!(places[0].state.as_deref(), Some("England"));
+        assert_eq!(places[0].country, "GB");
+        assert_eq!(places[0].latitude, 51.5073219);
+        assert_eq!(places[1].state, None);
+    }
+
+    #[tokio::test]
+    async fn test_search_with_rejected_key() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", mockito::Matcher::Any)
+            .with_status(401)
+            .with_body(r#"{"cod":401,"message":"Invalid API key."}"#)
+            .create_async()
+            .await;
+        let geocoder = OpenWeatherGeocoder::with_base_url("bad_key", &server.url());
+
+        assert!(geocoder.search("London", 5).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reverse_from_fixture() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/geo/1.0/reverse")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("lat".into(), "51.5098".into()),
+                mockito::Matcher::UrlEncoded("lon".into(), "-0.118".into()),
+            ]))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(REVERSE_FIXTURE)
+            .create_async()
+            .await;
+        let geocoder = OpenWeatherGeocoder::with_base_url("test_key", &server.url());
+
+        let locality = geocoder.reverse(51.5098, -0.118).await.unwrap().unwrap();
+
+        assert_eq!(locality.name, "City of Westminster");
+        assert_eq!(locality.region.as_deref(), Some("England"));
+        assert_eq!(locality.country, "GB");
+        assert_eq!(locality.timezone, None);
+    }
+
+    #[tokio::test]
+    async fn test_reverse_at_sea() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/geo/1.0/reverse")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_body("[]")
+            .create_async()
+            .await;
+        let geocoder = OpenWeatherGeocoder::with_base_url("test_key", &server.url());
+
+        assert_eq!(geocoder.reverse(0.0, -30.0).await.unwrap(), None);
+    }
+}
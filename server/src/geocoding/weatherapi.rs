@@ -0,0 +1,187 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use crate::geocoding::{Geocoder, Locality, Place};
+use reqwest;
+use tracing::debug;
+
+const WEATHERAPI_BASE_URL: &str = "http://api.weatherapi.com";
+
+/// WeatherAPI's `search.json` autocomplete and `timezone.json` lookup, which
+/// share the weather API key.
+pub struct WeatherApiGeocoder {
+    api_key: String,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl WeatherApiGeocoder {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self::with_base_url(api_key, WEATHERAPI_BASE_URL)
+    }
+
+    pub fn with_base_url(api_key: impl Into<String>, base_url: &str) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchMatch {
+    name: String,
+    /// Empty for places without a region
+    #[serde(default)]
+    region: String,
+    country: String,
+    lat: f64,
+    lon: f64,
+}
+
+#[derive(Deserialize)]
+struct TimezoneResponse {
+    location: TimezoneLocation,
+}
+
+#[derive(Deserialize)]
+struct TimezoneLocation {
+    name: String,
+    #[serde(default)]
+    region: String,
+    country: String,
+    tz_id: String,
+}
+
+#[async_trait]
+impl Geocoder for WeatherApiGeocoder {
+    fn name(&self) -> String {
+        "WeatherAPI".to_string()
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<Place>, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!("{}/v1/search.json", self.base_url);
+        debug!(query, limit, "Searching places on WeatherAPI");
+
+        // search.json has no limit parameter and answers with up to ten matches
+        let matches: Vec<SearchMatch> = self.client
+            .get(&url)
+            .query(&[("key", self.api_key.as_str()), ("q", query)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(matches
+            .into_iter()
+            .take(limit)
+            .map(|m| Place {
+                name: m.name,
+                state: Some(m.region).filter(|region| !region.is_empty()),
+                country: m.country,
+                latitude: m.lat,
+                longitude: m.lon,
+            })
+            .collect())
+    }
+
+    /// Uses `timezone.json`, the cheapest endpoint that names the nearest
+    /// place along with its time zone. WeatherAPI always finds a nearest place.
+    async fn reverse(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Option<Locality>, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!("{}/v1/timezone.json", self.base_url);
+        debug!(latitude, longitude, "Reverse geocoding on WeatherAPI");
+
+        let response: TimezoneResponse = self.client
+            .get(&url)
+            .query(&[("key", self.api_key.clone()), ("q", format!("{},{}", latitude, longitude))])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let location = response.location;
+        Ok(Some(Locality {
+            name: location.name,
+            region: Some(location.region).filter(|region| !region.is_empty()),
+            country: location.country,
+            timezone: Some(location.tz_id).filter(|tz| !tz.is_empty()),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEARCH_FIXTURE: &str = include_str!("../tests/fixtures/weatherapi_search.json");
+    const TIMEZONE_FIXTURE: &str = include_str!("../tests/fixtures/weatherapi_timezone.json");
+
+    #[tokio::test]
+    async fn test_search_from_fixture() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/v1/search.json")
+            .match_query(mockito::Matcher::UrlEncoded("q".into(), "Paris".into()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(SEARCH_FIXTURE)
+            .create_async()
+            .await;
+        let geocoder = WeatherApiGeocoder::with_base_url("test_key", &server.url());
+
+        let places = geocoder.search("Paris", 2).await.unwrap();
+
+        assert_eq!(places.len(), 2);
+        assert_eq!(places[0].name, "Paris");
+        assert_eq!(places[0].state.as_deref(), Some("Ile-de-France"));
+        assert_eq!(places[0].country, "France");
+        assert_eq!(places[1].country, "United States of America");
+        assert_eq!((places[1].latitude, places[1].longitude), (33.66, -95.56));
+    }
+
+    #[tokio::test]
+    async fn test_search_without_region() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", mockito::Matcher::Any)
+            .with_status(200)
+            .with_body(r#"[{"id": 1, "name": "Monaco", "region": "", "country": "Monaco", "lat": 43.73, "lon": 7.42}]"#)
+            .create_async()
+            .await;
+        let geocoder = WeatherApiGeocoder::with_base_url("test_key", &server.url());
+
+        let places = geocoder.search("Monaco", 5).await.unwrap();
+        assert_eq!(places[0].state, None);
+    }
+
+    #[tokio::test]
+    async fn test_reverse_from_fixture() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/v1/timezone.json")
+            .match_query(mockito::Matcher::UrlEncoded("q".into(), "48.8567,2.3508".into()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(TIMEZONE_FIXTURE)
+            .create_async()
+            .await;
+        let geocoder = WeatherApiGeocoder::with_base_url("test_key", &server.url());
+
+        let locality = geocoder.reverse(48.8567, 2.3508).await.unwrap().unwrap();
+
+        assert_eq!(locality.name, "Paris");
+        assert_eq!(locality.region.as_deref(), Some("Ile-de-France"));
+        assert_eq!(locality.country, "France");
+        assert_eq!(locality.timezone.as_deref(), Some("Europe/Paris"));
+    }
+}
@@ -1,13 +1,21 @@
 pub mod providers;
+pub mod astronomy;
 pub mod proto;
 pub mod config;
 pub mod service;
 pub mod error;
+pub mod geocoding;
+pub mod storage;
 #[cfg(test)]
 mod tests;
 
 
 use tonic::transport::Server;
+use config::Settings;
+use std::sync::Arc;
+use providers::{CachedProvider, CoalescingProvider, PersistentProvider, ProviderRegistry, WeatherProvider};
+use storage::WeatherStore;
+use geocoding::GeocoderChain;
 use service::weather::WeatherServiceImpl;
 use proto::weather::weather_service_server::WeatherServiceServer;
 use tracing::info;
@@ -27,7 +35,39 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .init();
 
     let addr = "0.0.0.0:50051".parse()?;
-    let weather_service = WeatherServiceImpl::new();
+    let settings = Settings::new()?;
+    let mut registry = ProviderRegistry::from_settings(&settings.providers, &settings.cache)?;
+    // WatchWeather pollers refresh on their own schedule, so they skip the layers below
+    let upstream = registry.clone();
+    let store = if settings.storage.enabled {
+        let store = Arc::new(
+            WeatherStore::connect(&settings.storage.database_url, settings.storage.precision).await?,
+        );
+        let storage = settings.storage.capped_by(&settings.cache);
+        registry.wrap_providers(|provider| -> Arc<dyn WeatherProvider> {
+            Arc::new(PersistentProvider::new(provider, store.clone(), &storage))
+        });
+        Some(store)
+    } else {
+        None
+    };
+    // Concurrent identical requests that miss the cache share one upstream call
+    registry.wrap_providers(|provider| -> Arc<dyn WeatherProvider> {
+        Arc::new(CoalescingProvider::new(provider))
+    });
+    if settings.cache.enabled {
+        let cache = settings.cache.clone();
+        registry.wrap_providers(|provider| -> Arc<dyn WeatherProvider> {
+            Arc::new(CachedProvider::new(provider, &cache))
+        });
+    }
+    let geocoders = GeocoderChain::from_settings(&settings.providers, &settings.geocoding)?;
+    let mut weather_service = WeatherServiceImpl::new(registry)
+        .with_watch(upstream, &settings.watch)
+        .with_geocoders(geocoders);
+    if let Some(store) = store {
+        weather_service = weather_service.with_store(store, settings.storage.max_stale_secs);
+    }
 
     info!("Weather server listening on {}", addr);
 
@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+use crate::providers::dates::local_date;
+use crate::providers::traits::{ConditionCode, DayForecast};
+
+/// A forecast entry covering part of a day, in canonical units.
+#[derive(Debug, Clone)]
+pub(crate) struct Slot {
+    /// Unix seconds at the start of the slot
+    pub timestamp: i64,
+    pub temperature: f32,
+    pub condition: String,
+    pub condition_code: ConditionCode,
+    /// Percent, 0 to 100
+    pub precipitation_probability: f32,
+    /// Millimetres falling during the slot
+    pub precipitation_mm: f32,
+}
+
+/// Rolls `slots` up into the first `days` local calendar days, where local
+/// time is UTC shifted by `utc_offset_secs`.
+pub(crate) fn daily(slots: Vec<Slot>, utc_offset_secs: i64, days: usize) -> Vec<DayForecast> {
+    // ISO dates sort chronologically
+    let mut by_day: BTreeMap<String, Vec<Slot>> = BTreeMap::new();
+    for slot in slots {
+        by_day.entry(local_date(slot.timestamp, utc_offset_secs)).or_default().push(slot);
+    }
+
+    by_day
+        .into_iter()
+        .take(days)
+        .map(|(date, mut slots)| {
+            slots.sort_by_key(|s| s.timestamp);
+            summarize(date, &slots)
+        })
+        .collect()
+}
+
+fn summarize(date: String, slots: &[Slot]) -> DayForecast {
+    let max_temp = slots.iter().map(|s| s.temperature).reduce(f32::max);
+    let min_temp = slots.iter().map(|s| s.temperature).reduce(f32::min);
+    let (condition, condition_code) = dominant(slots);
+
+    DayForecast {
+        date,
+        max_temp,
+        min_temp,
+        condition,
+        condition_code,
+        precipitation_probability: Some(slots.iter().map(|s| s.precipitation_probability).fold(0.0, f32::max)),
+        precipitation_mm: Some(slots.iter().map(|s| s.precipitation_mm).sum()),
+        locality: None,
+    }
+}
+
+/// Most frequent condition code among `slots` (ties go to the earliest),
+/// with the wording of its first occurrence.
+fn dominant(slots: &[Slot]) -> (String, ConditionCode) {
+    let mut counts: Vec<(&Slot, usize)> = Vec::new();
+    for slot in slots {
+        match counts.iter_mut().find(|(seen, _)| seen.condition_code == slot.condition_code) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((slot, 1)),
+        }
+    }
+
+    let mut best: Option<(&Slot, usize)> = None;
+    for (slot, count) in counts {
+        let better = match best {
+            Some((_, best_count)) => count > best_count,
+            None => true,
+        };
+        if better {
+            best = Some((slot, count));
+        }
+    }
+
+    match best {
+        Some((slot, _)) => (slot.condition.clone(), slot.condition_code),
+        None => ("Unknown".to_string(), ConditionCode::Unknown),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot(timestamp: i64, temperature: f32, code: ConditionCode) -> Slot {
+        Slot {
+            timestamp,
+            temperature,
+            condition: format!("{:?}", code),
+            condition_code: code,
+            precipitation_probability: 0.0,
+            precipitation_mm: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_daily_is_chronological_and_truncated() {
+        let slots = (0..16)
+            .rev()
+            .map(|i| slot(1710892800 + i * 3 * 3600, i as f32, ConditionCode::Clear))
+            .collect();
+
+        let days = daily(slots, 0, 1);
+
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].date, "2024-03-20");
+        assert_eq!(days[0].min_temp, Some(0.0));
+        assert_eq!(days[0].max_temp, Some(7.0));
+    }
+
+    #[test]
+    fn test_dominant_condition() {
+        let slots = vec![
+            slot(0, 1.0, ConditionCode::Clear),
+            slot(1, 1.0, ConditionCode::LightRain),
+            slot(2, 1.0, ConditionCode::LightRain),
+            slot(3, 1.0, ConditionCode::Cloudy),
+        ];
+        assert_eq!(dominant(&slots).1, ConditionCode::LightRain);
+
+        // Ties go to whichever came first
+        assert_eq!(dominant(&slots[..2]).1, ConditionCode::Clear);
+    }
+
+    #[test]
+    fn test_precipitation_is_summed_and_chance_maxed() {
+        let mut wet = slot(0, 1.0, ConditionCode::Rain);
+        wet.precipitation_mm = 1.5;
+        wet.precipitation_probability = 80.0;
+        let mut damp = slot(3 * 3600, 1.0, ConditionCode::Drizzle);
+        damp.precipitation_mm = 0.25;
+        damp.precipitation_probability = 40.0;
+
+        let days = daily(vec![wet, damp], 0, 5);
+
+        assert_eq!(days[0].precipitation_mm, Some(1.75));
+        assert_eq!(days[0].precipitation_probability, Some(80.0));
+    }
+}
@@ -0,0 +1,202 @@
+//! Air quality indices computed from pollutant concentrations, so every
+//! provider is rated on the same scale regardless of what it reports itself.
+
+use crate::providers::traits::AirQuality;
+
+/// Litres per mole of air at 25 °C and 1 atm, for µg/m³ to ppb conversion.
+const MOLAR_VOLUME: f64 = 24.45;
+
+const O3_MOLAR_MASS: f64 = 48.00;
+const NO2_MOLAR_MASS: f64 = 46.01;
+const SO2_MOLAR_MASS: f64 = 64.07;
+const CO_MOLAR_MASS: f64 = 28.01;
+
+/// Index range of each US EPA category, shared by every pollutant.
+const EPA_INDEX: [(f64, f64); 6] = [
+    (0.0, 50.0),
+    (51.0, 100.0),
+    (101.0, 150.0),
+    (151.0, 200.0),
+    (201.0, 300.0),
+    (301.0, 500.0),
+];
+
+/// Concentration breakpoints per EPA category (2024 revision), in the
+/// units and precision the EPA truncates to.
+const EPA_PM2_5: [(f64, f64); 6] = [
+    (0.0, 9.0),
+    (9.1, 35.4),
+    (35.5, 55.4),
+    (55.5, 125.4),
+    (125.5, 225.4),
+    (225.5, 325.4),
+];
+const EPA_PM10: [(f64, f64); 6] = [
+    (0.0, 54.0),
+    (55.0, 154.0),
+    (155.0, 254.0),
+    (255.0, 354.0),
+    (355.0, 424.0),
+    (425.0, 604.0),
+];
+/// 8-hour ozone, ppb. The 8-hour scale stops at "very unhealthy".
+const EPA_O3: [(f64, f64); 5] = [
+    (0.0, 54.0),
+    (55.0, 70.0),
+    (71.0, 85.0),
+    (86.0, 105.0),
+    (106.0, 200.0),
+];
+/// 1-hour NO2, ppb.
+const EPA_NO2: [(f64, f64); 6] = [
+    (0.0, 53.0),
+    (54.0, 100.0),
+    (101.0, 360.0),
+    (361.0, 649.0),
+    (650.0, 1249.0),
+    (1250.0, 2049.0),
+];
+/// 1-hour SO2, ppb.
+const EPA_SO2: [(f64, f64); 6] = [
+    (0.0, 35.0),
+    (36.0, 75.0),
+    (76.0, 185.0),
+    (186.0, 304.0),
+    (305.0, 604.0),
+    (605.0, 1004.0),
+];
+/// 8-hour CO, ppm.
+const EPA_CO: [(f64, f64); 6] = [
+    (0.0, 4.4),
+    (4.5, 9.4),
+    (9.5, 12.4),
+    (12.5, 15.4),
+    (15.5, 30.4),
+    (30.5, 50.4),
+];
+
+/// Upper bounds in µg/m³ of European AQI levels 1 (good) to 5 (very poor);
+/// anything above is level 6 (extremely poor).
+const EU_PM2_5: [f64; 5] = [10.0, 20.0, 25.0, 50.0, 75.0];
+const EU_PM10: [f64; 5] = [20.0, 40.0, 50.0, 100.0, 150.0];
+const EU_NO2: [f64; 5] = [40.0, 90.0, 120.0, 230.0, 340.0];
+const EU_O3: [f64; 5] = [50.0, 100.0, 130.0, 240.0, 380.0];
+const EU_SO2: [f64; 5] = [100.0, 200.0, 350.0, 500.0, 750.0];
+
+impl AirQuality {
+    /// US EPA Air Quality Index, 0 to 500: the highest pollutant sub-index.
+    /// Current concentrations stand in for the 8- and 24-hour averages the
+    /// EPA scale is defined on. `None` when no rated pollutant is known.
+    pub fn us_epa_index(&self) -> Option<u32> {
+        let ppb = |value: Option<f64>, molar_mass: f64| value.map(|v| v * MOLAR_VOLUME / molar_mass);
+
+        [
+            self.pm2_5.map(|c| epa_sub_index(truncate(c, 1), &EPA_PM2_5)),
+            self.pm10.map(|c| epa_sub_index(truncate(c, 0), &EPA_PM10)),
+            ppb(self.o3, O3_MOLAR_MASS).map(|c| epa_sub_index(truncate(c, 0), &EPA_O3)),
+            ppb(self.no2, NO2_MOLAR_MASS).map(|c| epa_sub_index(truncate(c, 0), &EPA_NO2)),
+            ppb(self.so2, SO2_MOLAR_MASS).map(|c| epa_sub_index(truncate(c, 0), &EPA_SO2)),
+            ppb(self.co, CO_MOLAR_MASS).map(|c| epa_sub_index(truncate(c / 1000.0, 1), &EPA_CO)),
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+    }
+
+    /// European Air Quality Index level, 1 (good) to 6 (extremely poor): the
+    /// worst level among the rated pollutants. `None` when none is known.
+    pub fn european_index(&self) -> Option<u32> {
+        [
+            self.pm2_5.map(|c| eu_level(c, &EU_PM2_5)),
+            self.pm10.map(|c| eu_level(c, &EU_PM10)),
+            self.no2.map(|c| eu_level(c, &EU_NO2)),
+            self.o3.map(|c| eu_level(c, &EU_O3)),
+            self.so2.map(|c| eu_level(c, &EU_SO2)),
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+    }
+}
+
+/// Linear interpolation within the category containing `concentration`;
+/// values past the last breakpoint are capped at its upper index.
+fn epa_sub_index(concentration: f64, breakpoints: &[(f64, f64)]) -> u32 {
+    let concentration = concentration.max(0.0);
+    for (&(low, high), &(index_low, index_high)) in breakpoints.iter().zip(EPA_INDEX.iter()) {
+        if concentration <= high {
+            let index = (index_high - index_low) / (high - low) * (concentration.max(low) - low) + index_low;
+            return index.round() as u32;
+        }
+    }
+    EPA_INDEX[breakpoints.len() - 1].1 as u32
+}
+
+fn eu_level(concentration: f64, upper_bounds: &[f64; 5]) -> u32 {
+    upper_bounds
+        .iter()
+        .position(|&bound| concentration <= bound)
+        .map_or(6, |level| level as u32 + 1)
+}
+
+/// Drops digits beyond `decimals`, as the EPA does before looking up a
+/// breakpoint.
+fn truncate(value: f64, decimals: i32) -> f64 {
+    let scale = 10f64.powi(decimals);
+    (value * scale + 1e-9).floor() / scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_epa_sub_index_interpolates_within_category() {
+        assert_eq!(epa_sub_index(0.0, &EPA_PM2_5), 0);
+        assert_eq!(epa_sub_index(9.0, &EPA_PM2_5), 50);
+        assert_eq!(epa_sub_index(35.4, &EPA_PM2_5), 100);
+        assert_eq!(epa_sub_index(22.2, &EPA_PM2_5), 75);
+        assert_eq!(epa_sub_index(154.0, &EPA_PM10), 100);
+        // Beyond the scale
+        assert_eq!(epa_sub_index(900.0, &EPA_PM2_5), 500);
+        assert_eq!(epa_sub_index(250.0, &EPA_O3), 300);
+    }
+
+    #[test]
+    fn test_us_epa_index_takes_worst_pollutant() {
+        let air = AirQuality {
+            pm2_5: Some(7.4),
+            pm10: Some(9.8),
+            // About 66 ppb, moderate
+            o3: Some(130.0),
+            no2: Some(13.5),
+            so2: Some(3.2),
+            co: Some(230.3),
+        };
+
+        assert_eq!(air.us_epa_index(), Some(87));
+    }
+
+    #[test]
+    fn test_european_index_takes_worst_pollutant() {
+        let air = AirQuality {
+            pm2_5: Some(7.4),
+            pm10: Some(45.0),
+            no2: Some(13.5),
+            ..Default::default()
+        };
+
+        assert_eq!(air.european_index(), Some(3));
+        assert_eq!(eu_level(10.0, &EU_PM2_5), 1);
+        assert_eq!(eu_level(800.0, &EU_PM2_5), 6);
+    }
+
+    #[test]
+    fn test_indices_absent_without_pollutants() {
+        let air = AirQuality { co: Some(230.3), ..Default::default() };
+
+        assert_eq!(AirQuality::default().us_epa_index(), None);
+        assert_eq!(air.european_index(), None);
+        assert_eq!(air.us_epa_index(), Some(2));
+    }
+}
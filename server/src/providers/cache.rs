@@ -0,0 +1,273 @@
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use crate::config::CacheConfig;
+use crate::providers::ttl::TtlLru;
+use crate::providers::traits::{
+    WeatherProvider, AirQuality, CurrentWeather, DayForecast, HistoricalDay, HourlyForecast, ProviderCapabilities,
+    WeatherAlert,
+};
+use tracing::debug;
+
+/// Coordinates rounded to the configured precision, so nearby lookups for
+/// the same city share an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Location {
+    latitude: i64,
+    longitude: i64,
+}
+
+/// TTL + LRU cache wrapped around another provider. Errors are never cached.
+pub struct CachedProvider {
+    inner: Arc<dyn WeatherProvider>,
+    scale: f64,
+    current_ttl: Duration,
+    forecast_ttl: Duration,
+    current: TtlLru<Location, CurrentWeather>,
+    forecast: TtlLru<(Location, i32), Vec<DayForecast>>,
+    hourly: TtlLru<(Location, i32), Vec<HourlyForecast>>,
+    alerts: TtlLru<Location, Vec<WeatherAlert>>,
+    air_quality: TtlLru<Location, AirQuality>,
+    historical: TtlLru<(Location, String), HistoricalDay>,
+}
+
+impl CachedProvider {
+    pub fn new(inner: Arc<dyn WeatherProvider>, config: &CacheConfig) -> Self {
+        let capacity = config.max_entries;
+        Self {
+            inner,
+            scale: 10f64.powi(config.precision as i32),
+            current_ttl: Duration::from_secs(config.current_ttl_secs),
+            forecast_ttl: Duration::from_secs(config.forecast_ttl_secs),
+            current: TtlLru::new(capacity),
+            forecast: TtlLru::new(capacity),
+            hourly: TtlLru::new(capacity),
+            alerts: TtlLru::new(capacity),
+            air_quality: TtlLru::new(capacity),
+            historical: TtlLru::new(capacity),
+        }
+    }
+
+    fn location(&self, latitude: f64, longitude: f64) -> Location {
+        Location {
+            latitude: (latitude * self.scale).round() as i64,
+            longitude: (longitude * self.scale).round() as i64,
+        }
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for CachedProvider {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn get_current_weather(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<CurrentWeather, Box<dyn std::error::Error + Send + Sync>> {
+        let key = self.location(latitude, longitude);
+        if let Some(weather) = self.current.get(&key) {
+            debug!(provider = %self.inner.name(), ?key, "Current weather cache hit");
+            return Ok(weather);
+        }
+        debug!(provider = %self.inner.name(), ?key, "Current weather cache miss");
+
+        let weather = self.inner.get_current_weather(latitude, longitude).await?;
+        self.current.insert(key, weather.clone(), self.current_ttl);
+        Ok(weather)
+    }
+
+    async fn get_forecast(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        days: i32,
+    ) -> Result<Vec<DayForecast>, Box<dyn std::error::Error + Send + Sync>> {
+        let key = (self.location(latitude, longitude), days);
+        if let Some(forecast) = self.forecast.get(&key) {
+            debug!(provider = %self.inner.name(), ?key, "Forecast cache hit");
+            return Ok(forecast);
+        }
+        debug!(provider = %self.inner.name(), ?key, "Forecast cache miss");
+
+        let forecast = self.inner.get_forecast(latitude, longitude, days).await?;
+        self.forecast.insert(key, forecast.clone(), self.forecast_ttl);
+        Ok(forecast)
+    }
+
+    async fn get_hourly_forecast(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        hours: i32,
+    ) -> Result<Vec<HourlyForecast>, Box<dyn std::error::Error + Send + Sync>> {
+        let key = (self.location(latitude, longitude), hours);
+        if let Some(hourly) = self.hourly.get(&key) {
+            debug!(provider = %self.inner.name(), ?key, "Hourly forecast cache hit");
+            return Ok(hourly);
+        }
+        debug!(provider = %self.inner.name(), ?key, "Hourly forecast cache miss");
+
+        let hourly = self.inner.get_hourly_forecast(latitude, longitude, hours).await?;
+        // Hourly slots start at the current hour, so they go stale as fast as current weather
+        self.hourly.insert(key, hourly.clone(), self.current_ttl);
+        Ok(hourly)
+    }
+
+    async fn get_alerts(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Vec<WeatherAlert>, Box<dyn std::error::Error + Send + Sync>> {
+        // Alerts change as quickly as current conditions
+        let key = self.location(latitude, longitude);
+        if let Some(alerts) = self.alerts.get(&key) {
+            debug!(provider = %self.inner.name(), ?key, "Alerts cache hit");
+            return Ok(alerts);
+        }
+        debug!(provider = %self.inner.name(), ?key, "Alerts cache miss");
+
+        let alerts = self.inner.get_alerts(latitude, longitude).await?;
+        self.alerts.insert(key, alerts.clone(), self.current_ttl);
+        Ok(alerts)
+    }
+
+    async fn get_air_quality(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<AirQuality, Box<dyn std::error::Error + Send + Sync>> {
+        let key = self.location(latitude, longitude);
+        if let Some(air) = self.air_quality.get(&key) {
+            debug!(provider = %self.inner.name(), ?key, "Air quality cache hit");
+            return Ok(air);
+        }
+        debug!(provider = %self.inner.name(), ?key, "Air quality cache miss");
+
+        let air = self.inner.get_air_quality(latitude, longitude).await?;
+        self.air_quality.insert(key, air.clone(), self.current_ttl);
+        Ok(air)
+    }
+
+    async fn get_historical(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        date: &str,
+    ) -> Result<HistoricalDay, Box<dyn std::error::Error + Send + Sync>> {
+        // Past days do not change, but the TTL still bounds what sits in memory
+        let key = (self.location(latitude, longitude), date.to_string());
+        if let Some(day) = self.historical.get(&key) {
+            debug!(provider = %self.inner.name(), ?key, "Historical cache hit");
+            return Ok(day);
+        }
+        debug!(provider = %self.inner.name(), ?key, "Historical cache miss");
+
+        let day = self.inner.get_historical(latitude, longitude, date).await?;
+        self.historical.insert(key, day.clone(), self.forecast_ttl);
+        Ok(day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::support::StubProvider;
+
+    fn config(max_entries: usize) -> CacheConfig {
+        CacheConfig {
+            enabled: true,
+            precision: 2,
+            current_ttl_secs: 60,
+            forecast_ttl_secs: 600,
+            max_entries,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_nearby_coordinates_share_entry() {
+        let stub = Arc::new(StubProvider::new("stub"));
+        let cached = CachedProvider::new(stub.clone(), &config(10));
+
+        cached.get_current_weather(51.5074, -0.1278).await.unwrap();
+        cached.get_current_weather(51.5071, -0.1281).await.unwrap();
+        assert_eq!(stub.calls(), 1);
+
+        cached.get_current_weather(51.52, -0.1278).await.unwrap();
+        assert_eq!(stub.calls(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_entries_expire_per_kind() {
+        let stub = Arc::new(StubProvider::new("stub"));
+        let cached = CachedProvider::new(stub.clone(), &config(10));
+
+        cached.get_current_weather(1.0, 2.0).await.unwrap();
+        cached.get_forecast(1.0, 2.0, 3).await.unwrap();
+        assert_eq!(stub.calls(), 2);
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+        cached.get_current_weather(1.0, 2.0).await.unwrap();
+        cached.get_forecast(1.0, 2.0, 3).await.unwrap();
+        assert_eq!(stub.calls(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_hourly_expires_with_current_weather() {
+        let stub = Arc::new(StubProvider::new("stub"));
+        let cached = CachedProvider::new(stub.clone(), &config(10));
+
+        cached.get_hourly_forecast(1.0, 2.0, 12).await.unwrap();
+        cached.get_hourly_forecast(1.0, 2.0, 12).await.unwrap();
+        assert_eq!(stub.calls(), 1);
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+        cached.get_hourly_forecast(1.0, 2.0, 12).await.unwrap();
+        assert_eq!(stub.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_forecast_keyed_by_days() {
+        let stub = Arc::new(StubProvider::new("stub"));
+        let cached = CachedProvider::new(stub.clone(), &config(10));
+
+        assert_eq!(cached.get_forecast(1.0, 2.0, 3).await.unwrap().len(), 3);
+        assert_eq!(cached.get_forecast(1.0, 2.0, 5).await.unwrap().len(), 5);
+        assert_eq!(cached.get_forecast(1.0, 2.0, 3).await.unwrap().len(), 3);
+        assert_eq!(stub.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_entry_is_evicted() {
+        let stub = Arc::new(StubProvider::new("stub"));
+        let cached = CachedProvider::new(stub.clone(), &config(2));
+
+        cached.get_current_weather(1.0, 1.0).await.unwrap();
+        cached.get_current_weather(2.0, 2.0).await.unwrap();
+        cached.get_current_weather(1.0, 1.0).await.unwrap();
+        cached.get_current_weather(3.0, 3.0).await.unwrap();
+        assert_eq!(stub.calls(), 3);
+
+        // (2, 2) was least recently used and got evicted; (1, 1) survived
+        cached.get_current_weather(1.0, 1.0).await.unwrap();
+        assert_eq!(stub.calls(), 3);
+        cached.get_current_weather(2.0, 2.0).await.unwrap();
+        assert_eq!(stub.calls(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_not_cached() {
+        let stub = Arc::new(StubProvider::new("stub").failing());
+        let cached = CachedProvider::new(stub.clone(), &config(10));
+
+        assert!(cached.get_current_weather(1.0, 2.0).await.is_err());
+        assert!(cached.get_current_weather(1.0, 2.0).await.is_err());
+        assert_eq!(stub.calls(), 2);
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use tokio::sync::broadcast;
+use crate::providers::traits::{
+    WeatherProvider, AirQuality, CurrentWeather, DayForecast, HistoricalDay, HourlyForecast, ProviderCapabilities,
+    WeatherAlert,
+};
+use tracing::debug;
+
+/// Result shared with every waiter; errors travel as their message since
+/// provider errors are not `Clone`.
+type Shared<T> = Result<T, String>;
+
+/// Upstream calls currently running, keyed by their arguments.
+struct InFlight<K, T> {
+    calls: Mutex<HashMap<K, broadcast::Sender<Shared<T>>>>,
+}
+
+impl<K: Hash + Eq + Clone, T: Clone> InFlight<K, T> {
+    fn new() -> Self {
+        Self {
+            calls: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Runs `call` unless an identical one is already running, in which case
+    /// its result is awaited instead.
+    async fn run<F, Fut>(&self, key: K, call: F) -> Result<T, Box<dyn std::error::Error + Send + Sync>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, Box<dyn std::error::Error + Send + Sync>>>,
+    {
+        let waiting = {
+            let mut calls = self.calls.lock().unwrap();
+            match calls.get(&key) {
+                Some(sender) => Some(sender.subscribe()),
+                None => {
+                    calls.insert(key.clone(), broadcast::channel(1).0);
+                    None
+                }
+            }
+        };
+
+        if let Some(mut receiver) = waiting {
+            debug!("Joining in-flight provider call");
+            return match receiver.recv().await {
+                Ok(shared) => shared.map_err(Into::into),
+                // The leading caller was cancelled before answering
+                Err(_) => call().await,
+            };
+        }
+
+        let leader = Leader { in_flight: self, key: Some(key) };
+        let result = call().await;
+        if let Some(sender) = leader.finish() {
+            let shared = match &result {
+                Ok(value) => Ok(value.clone()),
+                Err(e) => Err(e.to_string()),
+            };
+            // No receivers just means nobody joined this call
+            let _ = sender.send(shared);
+        }
+        result
+    }
+}
+
+/// Clears the in-flight entry even if the leading call is dropped midway,
+/// so waiters are released instead of hanging.
+struct Leader<'a, K: Hash + Eq, T> {
+    in_flight: &'a InFlight<K, T>,
+    key: Option<K>,
+}
+
+impl<K: Hash + Eq, T> Leader<'_, K, T> {
+    fn finish(mut self) -> Option<broadcast::Sender<Shared<T>>> {
+        let key = self.key.take()?;
+        self.in_flight.calls.lock().unwrap().remove(&key)
+    }
+}
+
+impl<K: Hash + Eq, T> Drop for Leader<'_, K, T> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.in_flight.calls.lock().unwrap().remove(&key);
+        }
+    }
+}
+
+/// Single-flight wrapper: concurrent identical requests share one call to
+/// the wrapped provider and all receive its result or error.
+pub struct CoalescingProvider {
+    inner: Arc<dyn WeatherProvider>,
+    current: InFlight<(u64, u64), CurrentWeather>,
+    forecast: InFlight<(u64, u64, i32), Vec<DayForecast>>,
+    hourly: InFlight<(u64, u64, i32), Vec<HourlyForecast>>,
+    alerts: InFlight<(u64, u64), Vec<WeatherAlert>>,
+    air_quality: InFlight<(u64, u64), AirQuality>,
+    historical: InFlight<(u64, u64, String), HistoricalDay>,
+}
+
+impl CoalescingProvider {
+    pub fn new(inner: Arc<dyn WeatherProvider>) -> Self {
+        Self {
+            inner,
+            current: InFlight::new(),
+            forecast: InFlight::new(),
+            hourly: InFlight::new(),
+            alerts: InFlight::new(),
+            air_quality: InFlight::new(),
+            historical: InFlight::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for CoalescingProvider {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn get_current_weather(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<CurrentWeather, Box<dyn std::error::Error + Send + Sync>> {
+        let key = (latitude.to_bits(), longitude.to_bits());
+        self.current
+            .run(key, || self.inner.get_current_weather(latitude, longitude))
+            .await
+    }
+
+    async fn get_forecast(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        days: i32,
+    ) -> Result<Vec<DayForecast>, Box<dyn std::error::Error + Send + Sync>> {
+        let key = (latitude.to_bits(), longitude.to_bits(), days);
+        self.forecast
+            .run(key, || self.inner.get_forecast(latitude, longitude, days))
+            .await
+    }
+
+    async fn get_hourly_forecast(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        hours: i32,
+    ) -> Result<Vec<HourlyForecast>, Box<dyn std::error::Error + Send + Sync>> {
+        let key = (latitude.to_bits(), longitude.to_bits(), hours);
+        self.hourly
+            .run(key, || self.inner.get_hourly_forecast(latitude, longitude, hours))
+            .await
+    }
+
+    async fn get_alerts(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Vec<WeatherAlert>, Box<dyn std::error::Error + Send + Sync>> {
+        let key = (latitude.to_bits(), longitude.to_bits());
+        self.alerts
+            .run(key, || self.inner.get_alerts(latitude, longitude))
+            .await
+    }
+
+    async fn get_air_quality(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<AirQuality, Box<dyn std::error::Error + Send + Sync>> {
+        let key = (latitude.to_bits(), longitude.to_bits());
+        self.air_quality
+            .run(key, || self.inner.get_air_quality(latitude, longitude))
+            .await
+    }
+
+    async fn get_historical(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        date: &str,
+    ) -> Result<HistoricalDay, Box<dyn std::error::Error + Send + Sync>> {
+        let key = (latitude.to_bits(), longitude.to_bits(), date.to_string());
+        self.historical
+            .run(key, || self.inner.get_historical(latitude, longitude, date))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::tests::support::StubProvider;
+
+    fn spawn_calls(provider: &Arc<CoalescingProvider>, count: usize) -> Vec<tokio::task::JoinHandle<Result<CurrentWeather, String>>> {
+        (0..count)
+            .map(|_| {
+                let provider = provider.clone();
+                tokio::spawn(async move {
+                    provider.get_current_weather(1.0, 2.0).await.map_err(|e| e.to_string())
+                })
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_one_upstream_call() {
+        let stub = Arc::new(StubProvider::new("stub").with_delay(Duration::from_millis(100)));
+        let provider = Arc::new(CoalescingProvider::new(stub.clone()));
+
+        for handle in spawn_calls(&provider, 10) {
+            assert_eq!(handle.await.unwrap().unwrap().temperature, 20.0);
+        }
+        assert_eq!(stub.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_error() {
+        let stub = Arc::new(StubProvider::new("stub").failing().with_delay(Duration::from_millis(100)));
+        let provider = Arc::new(CoalescingProvider::new(stub.clone()));
+
+        for handle in spawn_calls(&provider, 5) {
+            assert_eq!(handle.await.unwrap().unwrap_err(), "stub is unavailable");
+        }
+        assert_eq!(stub.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sequential_and_distinct_calls_are_not_shared() {
+        let stub = Arc::new(StubProvider::new("stub"));
+        let provider = CoalescingProvider::new(stub.clone());
+
+        provider.get_current_weather(1.0, 2.0).await.unwrap();
+        provider.get_current_weather(1.0, 2.0).await.unwrap();
+        provider.get_forecast(1.0, 2.0, 3).await.unwrap();
+        provider.get_forecast(1.0, 2.0, 4).await.unwrap();
+        assert_eq!(stub.calls(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_leader_releases_waiters() {
+        let stub = Arc::new(StubProvider::new("stub").with_delay(Duration::from_millis(100)));
+        let provider = Arc::new(CoalescingProvider::new(stub.clone()));
+
+        let leader = {
+            let provider = provider.clone();
+            tokio::spawn(async move { provider.get_current_weather(1.0, 2.0).await.map(|_| ()).map_err(|e| e.to_string()) })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let waiter = spawn_calls(&provider, 1).remove(0);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        leader.abort();
+
+        assert!(waiter.await.unwrap().is_ok());
+        assert_eq!(stub.calls(), 2);
+    }
+}
@@ -0,0 +1,158 @@
+//! Calendar arithmetic on Unix timestamps, enough for provider payloads
+//! without pulling in a date library.
+
+const SECS_PER_DAY: i64 = 86_400;
+
+/// `YYYY-MM-DD` of a Unix timestamp shifted by `utc_offset_secs`.
+pub(crate) fn local_date(timestamp: i64, utc_offset_secs: i64) -> String {
+    let (year, month, day) = civil_from_days((timestamp + utc_offset_secs).div_euclid(SECS_PER_DAY));
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Unix seconds of an RFC 3339 timestamp such as `2024-03-20T10:00:00-04:00`.
+/// A missing offset is read as UTC; fractional seconds are dropped.
+pub(crate) fn parse_rfc3339(text: &str) -> Option<i64> {
+    let (date, time) = text.trim().split_once(['T', ' '])?;
+    let (year, month, day) = parse_date(date)?;
+
+    let (clock, offset_secs) = if let Some(clock) = time.strip_suffix(['Z', 'z']) {
+        (clock, 0)
+    } else if let Some(sign_at) = time.rfind(['+', '-']) {
+        let (clock, offset) = time.split_at(sign_at);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let (hours, minutes) = offset[1..].split_once(':').unwrap_or((&offset[1..], "0"));
+        let offset_secs = hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+        (clock, sign * offset_secs)
+    } else {
+        (time, 0)
+    };
+
+    let mut fields = clock.split(':');
+    let hour: i64 = fields.next()?.parse().ok()?;
+    let minute: i64 = fields.next()?.parse().ok()?;
+    let second: i64 = match fields.next() {
+        Some(second) => second.split('.').next()?.parse().ok()?,
+        None => 0,
+    };
+
+    let days = days_from_civil(year, month, day);
+    Some(days * SECS_PER_DAY + hour * 3600 + minute * 60 + second - offset_secs)
+}
+
+/// `(year, month, day)` of a `YYYY-MM-DD` date, or `None` for a day the
+/// month does not have, such as `2023-02-29`.
+pub(crate) fn parse_date(date: &str) -> Option<(i64, u32, u32)> {
+    let mut parts = date.splitn(3, '-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let day = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+    Some((year, month, day))
+}
+
+/// Number of days in `month` (1 to 12) of `year`.
+pub(crate) fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Whether `date` has ended in every timezone by `now`, so observations
+/// for it are final. The last day to end is at UTC-12.
+pub(crate) fn has_ended_everywhere(date: &str, now: i64) -> bool {
+    match parse_date(date) {
+        Some((year, month, day)) => (days_from_civil(year, month, day) + 1) * SECS_PER_DAY + 12 * 3600 <= now,
+        None => false,
+    }
+}
+
+/// Proleptic Gregorian date of a day count since 1970-01-01, after
+/// Howard Hinnant's `civil_from_days`.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Day count since 1970-01-01 of a proleptic Gregorian date; the inverse of
+/// `civil_from_days`.
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_date() {
+        // 2024-03-20T00:00:00Z
+        assert_eq!(local_date(1710892800, 0), "2024-03-20");
+        assert_eq!(local_date(1710892800, -3600), "2024-03-19");
+        assert_eq!(local_date(1710892800 - 1, 3600), "2024-03-20");
+        assert_eq!(local_date(0, 0), "1970-01-01");
+        assert_eq!(local_date(-1, 0), "1969-12-31");
+        // Leap day
+        assert_eq!(local_date(1709164800, 0), "2024-02-29");
+    }
+
+    #[test]
+    fn test_days_round_trip() {
+        for days in [-800_000, -1, 0, 19_782, 19_802, 60_000] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(days_from_civil(2024, 3, 20), 19_802);
+    }
+
+    #[test]
+    fn test_parse_date_checks_month_length() {
+        assert_eq!(parse_date("2024-02-29"), Some((2024, 2, 29)));
+        assert_eq!(parse_date("2000-02-29"), Some((2000, 2, 29)));
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("1900-02-29"), None);
+        assert_eq!(parse_date("2023-04-30"), Some((2023, 4, 30)));
+        assert_eq!(parse_date("2023-04-31"), None);
+        assert_eq!(parse_date("2023-12-31"), Some((2023, 12, 31)));
+        assert_eq!(parse_date("2023-12-00"), None);
+    }
+
+    #[test]
+    fn test_has_ended_everywhere() {
+        // 2024-03-21T12:00:00Z, when 2024-03-20 ends on Baker Island
+        let end = 1710892800 + 86400 + 12 * 3600;
+        assert!(has_ended_everywhere("2024-03-20", end));
+        assert!(!has_ended_everywhere("2024-03-20", end - 1));
+        assert!(!has_ended_everywhere("someday", end));
+    }
+
+    #[test]
+    fn test_parse_rfc3339() {
+        assert_eq!(parse_rfc3339("2024-03-20T00:00:00Z"), Some(1710892800));
+        assert_eq!(parse_rfc3339("2024-03-20T10:00:00-04:00"), Some(1710892800 + 14 * 3600));
+        assert_eq!(parse_rfc3339("2024-03-20T05:30:00+05:30"), Some(1710892800));
+        assert_eq!(parse_rfc3339("2024-03-20 00:00"), Some(1710892800));
+        assert_eq!(parse_rfc3339("2024-03-20T00:00:00.250Z"), Some(1710892800));
+        assert_eq!(parse_rfc3339("2024-13-20T00:00:00Z"), None);
+        assert_eq!(parse_rfc3339("yesterday"), None);
+    }
+}
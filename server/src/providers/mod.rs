@@ -1,8 +1,24 @@
 mod traits;
 mod openweather;
 mod weatherapi;
+mod openmeteo;
+mod registry;
+mod cache;
+pub(crate) mod ttl;
+mod persistent;
+mod coalesce;
+mod aggregate;
+pub(crate) mod dates;
+mod aqi;
 
 pub use traits::WeatherProvider;
 pub use openweather::OpenWeatherProvider;
 pub use weatherapi::WeatherApiProvider;
-pub use self::traits::CurrentWeather;
\ No newline at end of file
+pub use openmeteo::OpenMeteoProvider;
+pub use registry::{ProviderEntry, ProviderRegistry};
+pub use cache::CachedProvider;
+pub use persistent::PersistentProvider;
+pub use coalesce::CoalescingProvider;
+pub use self::traits::{ConditionCode, CurrentWeather, DayForecast, HourlyForecast, ProviderCapabilities};
+pub use self::traits::{AirQuality, AlertSeverity, HistoricalDay, Unsupported, WeatherAlert};
+pub use self::traits::{DistanceUnit, PressureUnit, SpeedUnit, TemperatureUnit, Units};
@@ -0,0 +1,562 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use crate::providers::traits::{
+    WeatherProvider, ConditionCode, CurrentWeather, DayForecast, HourlyForecast, ProviderCapabilities, SpeedUnit,
+    AirQuality, HistoricalDay, Unsupported, WeatherAlert,
+};
+use reqwest;
+use tracing::debug;
+
+const OPENMETEO_BASE_URL: &str = "https://api.open-meteo.com";
+const OPENMETEO_ARCHIVE_URL: &str = "https://archive-api.open-meteo.com";
+
+pub struct OpenMeteoProvider {
+    base_url: String,
+    archive_url: String,
+    client: reqwest::Client,
+}
+
+impl Default for OpenMeteoProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OpenMeteoProvider {
+    // Open-Meteo is keyless, so no settings are needed
+    pub fn new() -> Self {
+        Self::with_base_urls(OPENMETEO_BASE_URL, OPENMETEO_ARCHIVE_URL)
+    }
+
+    /// Serves the forecast and archive APIs from one host.
+    pub fn with_base_url(base_url: &str) -> Self {
+        Self::with_base_urls(base_url, base_url)
+    }
+
+    pub fn with_base_urls(base_url: &str, archive_url: &str) -> Self {
+        OpenMeteoProvider {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            archive_url: archive_url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Maps a WMO weather interpretation code to the condition groups
+    /// used by the other providers.
+    fn weather_code_to_condition(code: i32) -> &'static str {
+        match code {
+            0 => "Clear",
+            1 | 2 => "Partly cloudy",
+            3 => "Clouds",
+            45 | 48 => "Fog",
+            51 | 53 | 55 | 56 | 57 => "Drizzle",
+            61 | 63 | 65 | 66 | 67 | 80 | 81 | 82 => "Rain",
+            71 | 73 | 75 | 77 | 85 | 86 => "Snow",
+            95 | 96 | 99 => "Thunderstorm",
+            _ => "Unknown",
+        }
+    }
+
+    /// Maps a WMO weather interpretation code onto the canonical taxonomy.
+    fn weather_code_to_condition_code(code: i32) -> ConditionCode {
+        match code {
+            0 => ConditionCode::Clear,
+            1 | 2 => ConditionCode::PartlyCloudy,
+            3 => ConditionCode::Overcast,
+            45 | 48 => ConditionCode::Fog,
+            51 | 53 | 55 => ConditionCode::Drizzle,
+            56 | 57 => ConditionCode::FreezingDrizzle,
+            61 => ConditionCode::LightRain,
+            63 => ConditionCode::Rain,
+            65 => ConditionCode::HeavyRain,
+            66 | 67 => ConditionCode::FreezingRain,
+            71 => ConditionCode::LightSnow,
+            73 | 77 => ConditionCode::Snow,
+            75 => ConditionCode::HeavySnow,
+            80..=82 => ConditionCode::RainShowers,
+            85 | 86 => ConditionCode::SnowShowers,
+            95 | 96 | 99 => ConditionCode::Thunderstorm,
+            _ => ConditionCode::Unknown,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenMeteoResponse {
+    current: Option<CurrentData>,
+    hourly: Option<HourlyData>,
+    daily: DailyData,
+}
+
+#[derive(Deserialize)]
+struct CurrentData {
+    time: String,
+    temperature_2m: f64,
+    relative_humidity_2m: f64,
+    weather_code: i32,
+    wind_speed_10m: f64,
+    wind_direction_10m: f64,
+    pressure_msl: Option<f64>,
+    is_day: Option<u8>,
+}
+
+#[derive(Deserialize)]
+struct HourlyData {
+    time: Vec<String>,
+    uv_index: Vec<Option<f64>>,
+    visibility: Vec<Option<f64>>,
+    /// Total over the hour before each time
+    precipitation: Vec<Option<f64>>,
+}
+
+#[derive(Deserialize)]
+struct OpenMeteoHourlyResponse {
+    hourly: HourlySeries,
+}
+
+#[derive(Deserialize)]
+struct HourlySeries {
+    time: Vec<i64>,
+    temperature_2m: Vec<Option<f64>>,
+    precipitation_probability: Vec<Option<f64>>,
+    weather_code: Vec<Option<i32>>,
+    wind_speed_10m: Vec<Option<f64>>,
+    wind_direction_10m: Vec<Option<f64>>,
+    #[serde(default)]
+    is_day: Vec<Option<u8>>,
+}
+
+#[derive(Deserialize)]
+struct DailyData {
+    time: Vec<String>,
+    weather_code: Vec<Option<i32>>,
+    temperature_2m_max: Vec<Option<f64>>,
+    temperature_2m_min: Vec<Option<f64>>,
+    #[serde(default)]
+    precipitation_probability_max: Vec<Option<f64>>,
+    #[serde(default)]
+    precipitation_sum: Vec<Option<f64>>,
+}
+
+#[derive(Deserialize)]
+struct OpenMeteoArchiveResponse {
+    daily: ArchiveDaily,
+}
+
+#[derive(Deserialize)]
+struct ArchiveDaily {
+    time: Vec<String>,
+    weather_code: Vec<Option<i32>>,
+    temperature_2m_max: Vec<Option<f64>>,
+    temperature_2m_min: Vec<Option<f64>>,
+    temperature_2m_mean: Vec<Option<f64>>,
+    precipitation_sum: Vec<Option<f64>>,
+    wind_speed_10m_max: Vec<Option<f64>>,
+}
+
+#[async_trait]
+impl WeatherProvider for OpenMeteoProvider {
+    fn name(&self) -> String {
+        "OpenMeteo".to_string()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            max_forecast_days: 16,
+            max_forecast_hours: 16 * 24,
+            supported_fields: vec![
+                "temperature",
+                "condition",
+                "wind_speed",
+                "wind_direction",
+                "humidity",
+                "visibility",
+                "pressure",
+                "uv_index",
+                "max_temp",
+                "min_temp",
+                "rain_chance",
+                "precipitation_mm",
+            ],
+            supports_alerts: false,
+            supports_air_quality: false,
+            supports_historical: true,
+        }
+    }
+
+    async fn get_current_weather(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<CurrentWeather, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!(
+            "{}/v1/forecast?latitude={}&longitude={}\
+             &current=temperature_2m,relative_humidity_2m,weather_code,wind_speed_10m,wind_direction_10m,pressure_msl,is_day\
+             &hourly=uv_index,visibility,precipitation\
+             &daily=weather_code,temperature_2m_max,temperature_2m_min,precipitation_probability_max\
+             &timezone=auto&forecast_days=1",
+            self.base_url, latitude, longitude
+        );
+        debug!(url = %url, "Fetching weather from Open-Meteo");
+
+        let response = self.client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        debug!(status = ?response.status(), "Open-Meteo response status");
+
+        let weather_response: OpenMeteoResponse = response.json().await?;
+        let current = weather_response.current
+            .ok_or("Open-Meteo response is missing current data")?;
+
+        // Hourly series are indexed by local time; pick the hour the current
+        // observation falls into for the values `current` does not carry.
+        let hour = current.time.get(..13).unwrap_or_default();
+        // `current=precipitation` only covers the last 15 minutes, whereas the
+        // hourly entry at the start of this hour sums the full hour before it.
+        let (uv_index, visibility, precipitation_mm) = weather_response.hourly
+            .and_then(|hourly| {
+                let index = hourly.time.iter().position(|t| t.starts_with(hour))?;
+                Some((
+                    hourly.uv_index.get(index).copied().flatten(),
+                    hourly.visibility.get(index).copied().flatten(),
+                    hourly.precipitation.get(index).copied().flatten(),
+                ))
+            })
+            .unwrap_or((None, None, None));
+
+        let daily = &weather_response.daily;
+        let weather = CurrentWeather {
+            temperature: current.temperature_2m,
+            humidity: current.relative_humidity_2m,
+            condition: Self::weather_code_to_condition(current.weather_code).to_string(),
+            condition_code: Self::weather_code_to_condition_code(current.weather_code),
+            is_day: current.is_day.map(|d| d == 1),
+            // Open-Meteo defaults to km/h wind and metres of visibility
+            wind_speed: SpeedUnit::KilometersPerHour.to_canonical(current.wind_speed_10m),
+            wind_direction: current.wind_direction_10m,
+            uv_index,
+            visibility,
+            pressure: current.pressure_msl,
+            // Open-Meteo does not resolve coordinates to a country
+            country: String::new(),
+            max_temp: daily.temperature_2m_max.first().copied().flatten(),
+            min_temp: daily.temperature_2m_min.first().copied().flatten(),
+            precipitation_probability: daily.precipitation_probability_max.first().copied().flatten(),
+            precipitation_mm,
+            locality: None,
+        };
+
+        debug!(?weather, "Transformed weather data");
+        Ok(weather)
+    }
+
+    async fn get_forecast(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        days: i32,
+    ) -> Result<Vec<DayForecast>, Box<dyn std::error::Error + Send + Sync>> {
+        // Open-Meteo serves at most 16 days of daily forecast
+        let days = days.clamp(1, 16);
+        let url = format!(
+            "{}/v1/forecast?latitude={}&longitude={}\
+             &daily=weather_code,temperature_2m_max,temperature_2m_min,precipitation_probability_max,precipitation_sum\
+             &timezone=auto&forecast_days={}",
+            self.base_url, latitude, longitude, days
+        );
+        debug!(url = %url, "Fetching forecast from Open-Meteo");
+
+        let response: OpenMeteoResponse = self.client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let daily = response.daily;
+        let result = daily.time
+            .into_iter()
+            .enumerate()
+            .take(days as usize)
+            .map(|(i, date)| {
+                let code = daily.weather_code.get(i).copied().flatten().unwrap_or(-1);
+
+                DayForecast {
+                    date,
+                    max_temp: daily.temperature_2m_max.get(i).copied().flatten().map(|t| t as f32),
+                    min_temp: daily.temperature_2m_min.get(i).copied().flatten().map(|t| t as f32),
+                    condition: Self::weather_code_to_condition(code).to_string(),
+                    condition_code: Self::weather_code_to_condition_code(code),
+                    precipitation_probability: daily.precipitation_probability_max.get(i)
+                        .copied().flatten().map(|p| p as f32),
+                    precipitation_mm: daily.precipitation_sum.get(i).copied().flatten().map(|p| p as f32),
+                    // Open-Meteo does not name places
+                    locality: None,
+                }
+            })
+            .collect();
+
+        Ok(result)
+    }
+
+    async fn get_hourly_forecast(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        hours: i32,
+    ) -> Result<Vec<HourlyForecast>, Box<dyn std::error::Error + Send + Sync>> {
+        let hours = hours.clamp(1, 16 * 24);
+        let url = format!(
+            "{}/v1/forecast?latitude={}&longitude={}\
+             &hourly=temperature_2m,precipitation_probability,weather_code,wind_speed_10m,wind_direction_10m,is_day\
+             &timeformat=unixtime&forecast_hours={}",
+            self.base_url, latitude, longitude, hours
+        );
+        debug!(url = %url, "Fetching hourly forecast from Open-Meteo");
+
+        let response: OpenMeteoHourlyResponse = self.client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let hourly = response.hourly;
+        let result = hourly.time
+            .iter()
+            .enumerate()
+            .take(hours as usize)
+            .filter_map(|(i, &timestamp)| {
+                let temperature = hourly.temperature_2m.get(i).copied().flatten()?;
+                let code = hourly.weather_code.get(i).copied().flatten().unwrap_or(-1);
+
+                Some(HourlyForecast {
+                    timestamp,
+                    temperature,
+                    precipitation_probability: hourly.precipitation_probability.get(i).copied().flatten(),
+                    wind_speed: SpeedUnit::KilometersPerHour
+                        .to_canonical(hourly.wind_speed_10m.get(i).copied().flatten().unwrap_or_default()),
+                    wind_direction: hourly.wind_direction_10m.get(i).copied().flatten().unwrap_or_default(),
+                    condition: Self::weather_code_to_condition(code).to_string(),
+                    condition_code: Self::weather_code_to_condition_code(code),
+                    is_day: hourly.is_day.get(i).copied().flatten().map(|d| d == 1),
+                })
+            })
+            .collect();
+
+        Ok(result)
+    }
+
+    async fn get_alerts(
+        &self,
+        _latitude: f64,
+        _longitude: f64,
+    ) -> Result<Vec<WeatherAlert>, Box<dyn std::error::Error + Send + Sync>> {
+        Err(Box::new(Unsupported {
+            provider: self.name(),
+            feature: "weather alerts",
+        }))
+    }
+
+    async fn get_air_quality(
+        &self,
+        _latitude: f64,
+        _longitude: f64,
+    ) -> Result<AirQuality, Box<dyn std::error::Error + Send + Sync>> {
+        Err(Box::new(Unsupported {
+            provider: self.name(),
+            feature: "air quality",
+        }))
+    }
+
+    async fn get_historical(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        date: &str,
+    ) -> Result<HistoricalDay, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!(
+            "{}/v1/archive?latitude={}&longitude={}&start_date={}&end_date={}\
+             &daily=weather_code,temperature_2m_max,temperature_2m_min,temperature_2m_mean,precipitation_sum,wind_speed_10m_max\
+             &wind_speed_unit=ms&timezone=auto",
+            self.archive_url, latitude, longitude, date, date
+        );
+        debug!(url = %url, "Fetching history from Open-Meteo");
+
+        let response: OpenMeteoArchiveResponse = self.client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        // The archive lags a few days behind; recent days come back as nulls
+        let daily = response.daily;
+        let index = daily.time.iter().position(|d| d == date);
+        let value = |series: &[Option<f64>]| index.and_then(|i| series.get(i).copied().flatten());
+        let (Some(max_temp), Some(min_temp)) = (value(&daily.temperature_2m_max), value(&daily.temperature_2m_min)) else {
+            return Err(format!("Open-Meteo archive has no data for {}", date).into());
+        };
+        let code = index.and_then(|i| daily.weather_code.get(i).copied().flatten()).unwrap_or(-1);
+
+        Ok(HistoricalDay {
+            date: date.to_string(),
+            max_temp,
+            min_temp,
+            avg_temp: value(&daily.temperature_2m_mean),
+            condition: Self::weather_code_to_condition(code).to_string(),
+            condition_code: Self::weather_code_to_condition_code(code),
+            precipitation_mm: value(&daily.precipitation_sum),
+            max_wind_speed: value(&daily.wind_speed_10m_max),
+            avg_humidity: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURRENT_FIXTURE: &str = include_str!("../tests/fixtures/openmeteo_current.json");
+    const FORECAST_FIXTURE: &str = include_str!("../tests/fixtures/openmeteo_forecast.json");
+    const HOURLY_FIXTURE: &str = include_str!("../tests/fixtures/openmeteo_hourly.json");
+    const ARCHIVE_FIXTURE: &str = include_str!("../tests/fixtures/openmeteo_archive.json");
+
+    async fn serve_fixture(body: &str) -> mockito::ServerGuard {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", mockito::Matcher::Regex("^/v1/forecast".to_string()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create_async()
+            .await;
+        server
+    }
+
+    #[test]
+    fn test_weather_code_to_condition() {
+        assert_eq!(OpenMeteoProvider::weather_code_to_condition(0), "Clear");
+        assert_eq!(OpenMeteoProvider::weather_code_to_condition(3), "Clouds");
+        assert_eq!(OpenMeteoProvider::weather_code_to_condition(63), "Rain");
+        assert_eq!(OpenMeteoProvider::weather_code_to_condition(75), "Snow");
+        assert_eq!(OpenMeteoProvider::weather_code_to_condition(99), "Thunderstorm");
+        assert_eq!(OpenMeteoProvider::weather_code_to_condition(42), "Unknown");
+    }
+
+    #[test]
+    fn test_weather_code_to_condition_code() {
+        assert_eq!(OpenMeteoProvider::weather_code_to_condition_code(0), ConditionCode::Clear);
+        assert_eq!(OpenMeteoProvider::weather_code_to_condition_code(3), ConditionCode::Overcast);
+        assert_eq!(OpenMeteoProvider::weather_code_to_condition_code(57), ConditionCode::FreezingDrizzle);
+        assert_eq!(OpenMeteoProvider::weather_code_to_condition_code(81), ConditionCode::RainShowers);
+        assert_eq!(OpenMeteoProvider::weather_code_to_condition_code(42), ConditionCode::Unknown);
+    }
+
+    #[tokio::test]
+    async fn test_get_current_weather_from_fixture() {
+        let server = serve_fixture(CURRENT_FIXTURE).await;
+        let provider = OpenMeteoProvider::with_base_url(&server.url());
+
+        let weather = provider.get_current_weather(52.52, 13.41).await.unwrap();
+
+        assert_eq!(weather.temperature, 13.4);
+        assert_eq!(weather.humidity, 62.0);
+        assert_eq!(weather.condition, "Clouds");
+        assert_eq!(weather.condition_code, ConditionCode::Overcast);
+        assert_eq!(weather.is_day, Some(true));
+        assert!((weather.wind_speed - 14.8 / 3.6).abs() < 1e-9);
+        assert_eq!(weather.wind_direction, 247.0);
+        assert_eq!(weather.uv_index, Some(3.4));
+        assert_eq!(weather.visibility, Some(18000.0));
+        assert_eq!(weather.pressure, Some(1015.2));
+        assert_eq!(weather.max_temp, Some(15.1));
+        assert_eq!(weather.min_temp, Some(6.7));
+        assert_eq!(weather.precipitation_probability, Some(65.0));
+        // The hourly total ending at 12:00, not a 15-minute sum
+        assert_eq!(weather.precipitation_mm, Some(0.8));
+    }
+
+    #[tokio::test]
+    async fn test_get_forecast_from_fixture() {
+        let server = serve_fixture(FORECAST_FIXTURE).await;
+        let provider = OpenMeteoProvider::with_base_url(&server.url());
+
+        let forecast = provider.get_forecast(52.52, 13.41, 2).await.unwrap();
+
+        assert_eq!(forecast.len(), 2);
+        assert_eq!(forecast[0].date, "2024-03-20");
+        assert_eq!(forecast[0].max_temp, Some(15.1));
+        assert_eq!(forecast[0].min_temp, Some(6.7));
+        assert_eq!(forecast[0].condition, "Rain");
+        assert_eq!(forecast[0].precipitation_probability, Some(65.0));
+        assert_eq!(forecast[0].precipitation_mm, Some(4.2));
+        assert_eq!(forecast[1].date, "2024-03-21");
+        assert_eq!(forecast[1].condition, "Clear");
+    }
+
+    #[tokio::test]
+    async fn test_get_hourly_forecast_from_fixture() {
+        let server = serve_fixture(HOURLY_FIXTURE).await;
+        let provider = OpenMeteoProvider::with_base_url(&server.url());
+
+        let hourly = provider.get_hourly_forecast(52.52, 13.41, 5).await.unwrap();
+
+        assert_eq!(hourly.len(), 5);
+        assert_eq!(hourly[0].timestamp, 1710892800);
+        assert_eq!(hourly[0].temperature, 7.1);
+        assert_eq!(hourly[3].precipitation_probability, Some(60.0));
+        assert_eq!(hourly[3].condition, "Rain");
+        assert_eq!(hourly[3].condition_code, ConditionCode::Rain);
+        assert_eq!(hourly[0].is_day, Some(false));
+        assert_eq!(hourly[4].is_day, Some(true));
+        assert_eq!(hourly[3].wind_direction, 255.0);
+        assert_eq!(hourly[4].precipitation_probability, None);
+    }
+
+    #[tokio::test]
+    async fn test_get_current_weather_upstream_error() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", mockito::Matcher::Any)
+            .with_status(400)
+            .with_body(r#"{"error":true,"reason":"Latitude must be in range of -90 to 90°."}"#)
+            .create_async()
+            .await;
+        let provider = OpenMeteoProvider::with_base_url(&server.url());
+
+        assert!(provider.get_current_weather(120.0, 13.41).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_historical_from_fixture() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", mockito::Matcher::Regex("^/v1/archive".to_string()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(ARCHIVE_FIXTURE)
+            .create_async()
+            .await;
+        let provider = OpenMeteoProvider::with_base_url(&server.url());
+
+        let day = provider.get_historical(52.52, 13.41, "2024-03-10").await.unwrap();
+        assert_eq!(day.date, "2024-03-10");
+        assert_eq!(day.max_temp, 12.4);
+        assert_eq!(day.min_temp, 3.1);
+        assert_eq!(day.avg_temp, Some(7.6));
+        assert_eq!(day.condition_code, ConditionCode::Drizzle);
+        assert_eq!(day.precipitation_mm, Some(1.2));
+        assert_eq!(day.max_wind_speed, Some(6.4));
+        assert_eq!(day.avg_humidity, None);
+
+        // Not yet in the archive
+        assert!(provider.get_historical(52.52, 13.41, "2024-03-11").await.is_err());
+    }
+}
@@ -1,31 +1,185 @@
+use std::time::Duration;
 use async_trait::async_trait;
 use serde::Deserialize;
-use crate::providers::traits::{WeatherProvider, CurrentWeather, DayForecast};
-use crate::config::Settings;
-use once_cell::sync::Lazy;
+use crate::config::CacheConfig;
+use crate::geocoding::Locality;
+use crate::providers::aggregate::{self, Slot};
+use crate::providers::dates;
+use crate::providers::ttl::TtlLru;
+use crate::providers::traits::{
+    WeatherProvider, ConditionCode, CurrentWeather, DayForecast, HourlyForecast, ProviderCapabilities,
+    AirQuality, Unsupported, WeatherAlert,
+};
+use crate::storage::unix_now;
 use reqwest;
-use std::collections::HashMap;
-use tracing::debug;
+use tracing::{debug, warn};
 
-// Static provider instance
-static OPENWEATHER: Lazy<OpenWeatherProvider> = Lazy::new(|| {
-    let settings = Settings::new()
-        .expect("Failed to load settings");
-    
-    OpenWeatherProvider {
-        api_key: settings.providers.openweather_api_key.clone(),
-        client: reqwest::Client::new(),
+const OPENWEATHER_BASE_URL: &str = "https://api.openweathermap.org";
+
+/// What current weather takes from the forecast, because the current
+/// weather endpoint lacks it.
+#[derive(Debug, Clone)]
+struct Outlook {
+    /// Percent, over the coming day
+    precipitation_probability: f64,
+    /// Shift of the location's local time from UTC in seconds
+    utc_offset_secs: i64,
+    /// The first local day in the forecast, which late in the evening is
+    /// already tomorrow
+    first_day: Option<DayForecast>,
+}
+
+impl Outlook {
+    fn from_forecast(forecast: &OpenWeatherForecastResponse) -> Self {
+        let slots = OpenWeatherProvider::slots(forecast);
+        Self {
+            // Eight 3-hour entries cover the coming day
+            precipitation_probability: forecast.list.iter().take(8).map(|f| f.pop).fold(0.0, f64::max) * 100.0,
+            utc_offset_secs: forecast.city.timezone,
+            first_day: aggregate::daily(slots, forecast.city.timezone, 1).pop(),
+        }
+    }
+
+    /// Today's high and low in °C at `now`, counting the `current` reading
+    /// alongside the forecast slots still ahead. `None` when the forecast
+    /// holds nothing for today. The current endpoint's own
+    /// `temp_max`/`temp_min` span the city area, not the day.
+    fn today_range(&self, now: i64, current: f64) -> Option<(f64, f64)> {
+        let day = self.first_day.as_ref()?;
+        if day.date != dates::local_date(now, self.utc_offset_secs) {
+            return None;
+        }
+        let (max, min) = (day.max_temp?, day.min_temp?);
+        Some((current.max(max as f64), current.min(min as f64)))
     }
-});
+}
 
+/// OpenWeather's free 2.5 API. Its current weather lacks precipitation
+/// probability and the day's range, so those come from the /forecast
+/// endpoint: one extra call per location, kept like a cached forecast for
+/// `cache.forecast_ttl_secs` unless the cache is disabled.
 pub struct OpenWeatherProvider {
     api_key: String,
+    base_url: String,
     client: reqwest::Client,
+    scale: f64,
+    outlook_ttl: Duration,
+    /// `None` when the cache is disabled, so every current call also fetches the forecast
+    outlooks: Option<TtlLru<(i64, i64), Outlook>>,
 }
 
 impl OpenWeatherProvider {
-    pub fn new() -> &'static Self {
-        &OPENWEATHER
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self::with_base_url(api_key, OPENWEATHER_BASE_URL)
+    }
+
+    pub fn with_base_url(api_key: impl Into<String>, base_url: &str) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+            scale: 0.0,
+            outlook_ttl: Duration::ZERO,
+            outlooks: None,
+        }
+        .with_cache(&CacheConfig::default())
+    }
+
+    /// Keeps the forecast behind current weather by `cache`'s precision,
+    /// forecast TTL and size.
+    pub fn with_cache(mut self, cache: &CacheConfig) -> Self {
+        self.scale = 10f64.powi(cache.precision as i32);
+        self.outlook_ttl = Duration::from_secs(cache.forecast_ttl_secs);
+        self.outlooks = cache.enabled.then(|| TtlLru::new(cache.max_entries));
+        self
+    }
+
+    /// Maps an OpenWeather condition id onto the canonical taxonomy, see
+    /// https://openweathermap.org/weather-conditions
+    fn condition_code(id: i32) -> ConditionCode {
+        match id {
+            200..=232 => ConditionCode::Thunderstorm,
+            300..=321 => ConditionCode::Drizzle,
+            500 => ConditionCode::LightRain,
+            501 => ConditionCode::Rain,
+            502..=504 => ConditionCode::HeavyRain,
+            511 => ConditionCode::FreezingRain,
+            520..=531 => ConditionCode::RainShowers,
+            600 => ConditionCode::LightSnow,
+            601 => ConditionCode::Snow,
+            602 => ConditionCode::HeavySnow,
+            611..=616 => ConditionCode::Sleet,
+            620..=622 => ConditionCode::SnowShowers,
+            701 => ConditionCode::Mist,
+            711 | 762 => ConditionCode::Smoke,
+            721 => ConditionCode::Haze,
+            731 | 751 | 761 => ConditionCode::Dust,
+            741 => ConditionCode::Fog,
+            771 => ConditionCode::Squall,
+            781 => ConditionCode::Tornado,
+            800 => ConditionCode::Clear,
+            801 | 802 => ConditionCode::PartlyCloudy,
+            803 => ConditionCode::Cloudy,
+            804 => ConditionCode::Overcast,
+            _ => ConditionCode::Unknown,
+        }
+    }
+
+    async fn fetch_forecast(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<OpenWeatherForecastResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!(
+            "{}/data/2.5/forecast?lat={}&lon={}&appid={}&units=metric",
+            self.base_url, latitude, longitude, self.api_key
+        );
+
+        Ok(self.client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// The outlook at a location, from the forecast fetched at most once
+    /// per `outlook_ttl`.
+    async fn outlook(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Outlook, Box<dyn std::error::Error + Send + Sync>> {
+        let key = ((latitude * self.scale).round() as i64, (longitude * self.scale).round() as i64);
+        if let Some(outlook) = self.outlooks.as_ref().and_then(|outlooks| outlooks.get(&key)) {
+            return Ok(outlook);
+        }
+
+        let outlook = Outlook::from_forecast(&self.fetch_forecast(latitude, longitude).await?);
+        if let Some(outlooks) = &self.outlooks {
+            outlooks.insert(key, outlook.clone(), self.outlook_ttl);
+        }
+        Ok(outlook)
+    }
+
+    /// Forecast entries as slots for `aggregate::daily`. Entries are 3-hourly
+    /// in UTC.
+    fn slots(response: &OpenWeatherForecastResponse) -> Vec<Slot> {
+        response.list
+            .iter()
+            .map(|f| Slot {
+                timestamp: f.dt,
+                temperature: f.main.temp,
+                condition: f.weather.first()
+                    .map(|w| w.main.clone())
+                    .unwrap_or_else(|| "Unknown".to_string()),
+                condition_code: f.weather.first()
+                    .map_or(ConditionCode::Unknown, |w| Self::condition_code(w.id)),
+                precipitation_probability: f.pop as f32 * 100.0,
+                precipitation_mm: f.precipitation_mm() as f32,
+            })
+            .collect()
     }
 }
 
@@ -34,21 +188,52 @@ struct OpenWeatherResponse {
     main: MainData,
     weather: Vec<WeatherData>,
     wind: WindData,
-    visibility: i32,
+    /// Omitted by some stations
+    visibility: Option<i32>,
     sys: SysData,
+    rain: Option<PrecipitationData>,
+    snow: Option<PrecipitationData>,
+    /// Nearest city; empty away from any
+    #[serde(default)]
+    name: String,
 }
 
+/// With `units=metric` OpenWeather already reports the canonical units:
+/// °C, m/s, metres and hPa.
 #[derive(Deserialize)]
 struct MainData {
     temp: f32,
     humidity: f32,
-    temp_max: f32,
-    temp_min: f32,
+    pressure: Option<f64>,
 }
 
 #[derive(Deserialize)]
 struct WeatherData {
+    id: i32,
     main: String,
+    /// Icon names end in `d` or `n` for day or night
+    #[serde(default)]
+    icon: String,
+}
+
+impl WeatherData {
+    fn is_day(&self) -> Option<bool> {
+        match self.icon.chars().last() {
+            Some('d') => Some(true),
+            Some('n') => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// Precipitation volume in mm; current weather reports the last hour,
+/// forecast entries the 3 hours the entry covers.
+#[derive(Deserialize)]
+struct PrecipitationData {
+    #[serde(rename = "1h", default)]
+    one_hour: f64,
+    #[serde(rename = "3h", default)]
+    three_hours: f64,
 }
 
 #[derive(Deserialize)]
@@ -60,13 +245,62 @@ struct WindData {
 #[derive(Deserialize)]
 struct OpenWeatherForecastResponse {
     list: Vec<ForecastData>,
+    city: CityData,
+}
+
+#[derive(Deserialize)]
+struct CityData {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    country: String,
+    /// Shift from UTC in seconds
+    #[serde(default)]
+    timezone: i64,
 }
 
 #[derive(Deserialize)]
 struct ForecastData {
-    dt_txt: String,
+    dt: i64,
     main: MainData,
     weather: Vec<WeatherData>,
+    wind: WindData,
+    /// Probability of precipitation, 0 to 1
+    #[serde(default)]
+    pop: f64,
+    rain: Option<PrecipitationData>,
+    snow: Option<PrecipitationData>,
+}
+
+impl ForecastData {
+    fn precipitation_mm(&self) -> f64 {
+        [&self.rain, &self.snow]
+            .into_iter()
+            .flatten()
+            .map(|p| p.three_hours)
+            .sum()
+    }
+}
+
+#[derive(Deserialize)]
+struct AirPollutionResponse {
+    list: Vec<AirPollutionData>,
+}
+
+#[derive(Deserialize)]
+struct AirPollutionData {
+    components: PollutantData,
+}
+
+/// Concentrations in µg/m³
+#[derive(Deserialize)]
+struct PollutantData {
+    pm2_5: Option<f64>,
+    pm10: Option<f64>,
+    o3: Option<f64>,
+    no2: Option<f64>,
+    so2: Option<f64>,
+    co: Option<f64>,
 }
 
 #[derive(Deserialize)]
@@ -80,40 +314,99 @@ impl WeatherProvider for OpenWeatherProvider {
         "OpenWeather".to_string()
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        // The 2.5 current weather endpoint reports no UV index
+        ProviderCapabilities {
+            max_forecast_days: 5,
+            max_forecast_hours: 120,
+            supported_fields: vec![
+                "temperature",
+                "condition",
+                "wind_speed",
+                "wind_direction",
+                "humidity",
+                "visibility",
+                "pressure",
+                "max_temp",
+                "min_temp",
+                "country",
+                "rain_chance",
+                "precipitation_mm",
+            ],
+            supports_alerts: false,
+            supports_air_quality: true,
+            supports_historical: false,
+        }
+    }
+
     async fn get_current_weather(
         &self,
         latitude: f64,
         longitude: f64,
     ) -> Result<CurrentWeather, Box<dyn std::error::Error + Send + Sync>> {
         let url = format!(
-            "https://api.openweathermap.org/data/2.5/weather?lat={}&lon={}&appid={}&units=metric",
-            latitude, longitude, self.api_key
+            "{}/data/2.5/weather?lat={}&lon={}&appid={}&units=metric",
+            self.base_url, latitude, longitude, self.api_key
         );
         debug!(url = %url, "Fetching weather");
 
-        let response = self.client
-            .get(&url)
-            .send()
-            .await?;
+        // The current weather endpoint has no precipitation probability, so
+        // it comes from the (mostly remembered) forecast
+        let (response, outlook) = tokio::join!(
+            self.client.get(&url).send(),
+            self.outlook(latitude, longitude),
+        );
+        let response = response?;
         
         debug!(status = ?response.status(), "OpenWeather API response status");
+        let response = response.error_for_status()?;
+
+        let outlook = match outlook {
+            Ok(outlook) => Some(outlook),
+            Err(e) => {
+                warn!(error = %e, "Failed to fetch precipitation probability");
+                None
+            }
+        };
         
         let response_text = response.text().await?;
         debug!(response = %response_text, "OpenWeather API response body");
         
         let weather_response: OpenWeatherResponse = serde_json::from_str(&response_text)?;
+        let today = outlook
+            .as_ref()
+            .and_then(|o| o.today_range(unix_now(), weather_response.main.temp as f64));
         
         let weather = CurrentWeather {
             temperature: weather_response.main.temp as f64,
             humidity: weather_response.main.humidity as f64,
             condition: weather_response.weather[0].main.clone(),
+            condition_code: Self::condition_code(weather_response.weather[0].id),
+            is_day: weather_response.weather[0].is_day(),
             wind_speed: weather_response.wind.speed as f64,
             wind_direction: weather_response.wind.deg as f64,
-            uv_index: 0.0,
-            visibility: (weather_response.visibility as f32 / 1000.0) as f64,
-            country: weather_response.sys.country,
-            max_temp: weather_response.main.temp_max as f64,
-            min_temp: weather_response.main.temp_min as f64,
+            uv_index: None,
+            visibility: weather_response.visibility.map(f64::from),
+            pressure: weather_response.main.pressure,
+            country: weather_response.sys.country.clone(),
+            max_temp: today.map(|(max, _)| max),
+            min_temp: today.map(|(_, min)| min),
+            precipitation_probability: outlook.map(|o| o.precipitation_probability),
+            // No rain or snow block means nothing fell
+            precipitation_mm: Some([&weather_response.rain, &weather_response.snow]
+                .into_iter()
+                .flatten()
+                .map(|p| p.one_hour)
+                .sum()),
+            // OpenWeather names the city but neither its region nor a time zone id
+            locality: Some(weather_response.name)
+                .filter(|name| !name.is_empty())
+                .map(|name| Locality {
+                    name,
+                    region: None,
+                    country: weather_response.sys.country.clone(),
+                    timezone: None,
+                }),
         };
         
         debug!(?weather, "Transformed weather data");
@@ -126,53 +419,367 @@ impl WeatherProvider for OpenWeatherProvider {
         longitude: f64,
         days: i32,
     ) -> Result<Vec<DayForecast>, Box<dyn std::error::Error + Send + Sync>> {
+        let response = self.fetch_forecast(latitude, longitude).await?;
+
+        // Days are the location's local days
+        let mut forecast = aggregate::daily(Self::slots(&response), response.city.timezone, days.max(0) as usize);
+        let city = response.city;
+        if !city.name.is_empty() {
+            let locality = Locality {
+                name: city.name,
+                region: None,
+                country: city.country,
+                timezone: None,
+            };
+            for day in &mut forecast {
+                day.locality = Some(locality.clone());
+            }
+        }
+        Ok(forecast)
+    }
+
+    async fn get_hourly_forecast(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        hours: i32,
+    ) -> Result<Vec<HourlyForecast>, Box<dyn std::error::Error + Send + Sync>> {
+        let response = self.fetch_forecast(latitude, longitude).await?;
+
+        // Entries are 3-hourly, so keep those starting within the window
+        let start = response.list.first().map(|f| f.dt).unwrap_or_default();
+        let end = start + i64::from(hours.max(0)) * 3600;
+
+        Ok(response.list
+            .into_iter()
+            .filter(|f| f.dt < end)
+            .map(|f| HourlyForecast {
+                timestamp: f.dt,
+                temperature: f.main.temp as f64,
+                precipitation_probability: Some(f.pop * 100.0),
+                wind_speed: f.wind.speed as f64,
+                wind_direction: f.wind.deg as f64,
+                condition: f.weather.first()
+                    .map(|w| w.main.clone())
+                    .unwrap_or_else(|| "Unknown".to_string()),
+                condition_code: f.weather.first()
+                    .map_or(ConditionCode::Unknown, |w| Self::condition_code(w.id)),
+                is_day: f.weather.first().and_then(WeatherData::is_day),
+            })
+            .collect())
+    }
+
+    async fn get_alerts(
+        &self,
+        _latitude: f64,
+        _longitude: f64,
+    ) -> Result<Vec<WeatherAlert>, Box<dyn std::error::Error + Send + Sync>> {
+        Err(Box::new(Unsupported {
+            provider: self.name(),
+            feature: "weather alerts",
+        }))
+    }
+
+    async fn get_air_quality(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<AirQuality, Box<dyn std::error::Error + Send + Sync>> {
         let url = format!(
-            "https://api.openweathermap.org/data/2.5/forecast?lat={}&lon={}&appid={}&units=metric",
-            latitude, longitude, self.api_key
+            "{}/data/2.5/air_pollution?lat={}&lon={}&appid={}",
+            self.base_url, latitude, longitude, self.api_key
         );
+        debug!("Fetching air pollution from OpenWeather");
 
-        let response: OpenWeatherForecastResponse = self.client
+        let response: AirPollutionResponse = self.client
             .get(&url)
             .send()
             .await?
+            .error_for_status()?
             .json()
             .await?;
+        let components = response.list
+            .into_iter()
+            .next()
+            .ok_or("OpenWeather returned no air pollution data")?
+            .components;
 
-        // Group forecasts by date
-        let mut daily_forecasts: HashMap<String, Vec<ForecastData>> = HashMap::new();
-        
-        for forecast in response.list {
-            let date = forecast.dt_txt.split_whitespace().next()
-                .unwrap_or_default()
-                .to_string();
-            daily_forecasts.entry(date).or_default().push(forecast);
-        }
+        Ok(AirQuality {
+            pm2_5: components.pm2_5,
+            pm10: components.pm10,
+            o3: components.o3,
+            no2: components.no2,
+            so2: components.so2,
+            co: components.co,
+        })
+    }
+}
 
-        let mut result: Vec<DayForecast> = daily_forecasts
-            .into_iter()
-            .take(days as usize)
-            .map(|(date, forecasts)| {
-                let (max_temp, min_temp) = forecasts.iter()
-                    .fold((f32::MIN, f32::MAX), |(max, min), f| {
-                        (max.max(f.main.temp), min.min(f.main.temp))
-                    });
-
-                // Use the most common condition for the day
-                let condition = forecasts.iter()
-                    .map(|f| f.weather[0].main.clone())
-                    .next()
-                    .unwrap_or_else(|| "Unknown".to_string());
-
-                DayForecast {
-                    date,
-                    max_temp,
-                    min_temp,
-                    condition,
-                }
-            })
-            .collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURRENT_FIXTURE: &str = include_str!("../tests/fixtures/openweather_current.json");
+    const FORECAST_FIXTURE: &str = include_str!("../tests/fixtures/openweather_forecast.json");
+    const AIR_POLLUTION_FIXTURE: &str = include_str!("../tests/fixtures/openweather_air_pollution.json");
+
+    #[test]
+    fn test_condition_code() {
+        assert_eq!(OpenWeatherProvider::condition_code(211), ConditionCode::Thunderstorm);
+        assert_eq!(OpenWeatherProvider::condition_code(502), ConditionCode::HeavyRain);
+        assert_eq!(OpenWeatherProvider::condition_code(521), ConditionCode::RainShowers);
+        assert_eq!(OpenWeatherProvider::condition_code(613), ConditionCode::Sleet);
+        assert_eq!(OpenWeatherProvider::condition_code(741), ConditionCode::Fog);
+        assert_eq!(OpenWeatherProvider::condition_code(800), ConditionCode::Clear);
+        assert_eq!(OpenWeatherProvider::condition_code(804), ConditionCode::Overcast);
+        assert_eq!(OpenWeatherProvider::condition_code(999), ConditionCode::Unknown);
+    }
+
+    async fn serve_fixture(path: &str, body: &str) -> mockito::ServerGuard {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", mockito::Matcher::Regex(format!("^{}", path)))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create_async()
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn test_get_hourly_forecast_from_fixture() {
+        let server = serve_fixture("/data/2.5/forecast", FORECAST_FIXTURE).await;
+        let provider = OpenWeatherProvider::with_base_url("test_key", &server.url());
+
+        let hourly = provider.get_hourly_forecast(51.5, -0.12, 12).await.unwrap();
+
+        assert_eq!(hourly.len(), 4);
+        assert_eq!(hourly[0].timestamp, 1710892800);
+        assert_eq!(hourly[1].timestamp - hourly[0].timestamp, 3 * 3600);
+        assert!((hourly[3].temperature - 13.2).abs() < 1e-4);
+        assert!((hourly[3].precipitation_probability.unwrap() - 10.0).abs() < 1e-9);
+        assert_eq!(hourly[3].condition, "Clouds");
+        assert_eq!(hourly[3].condition_code, ConditionCode::PartlyCloudy);
+        assert_eq!(hourly[0].is_day, Some(false));
+        assert_eq!(hourly[3].wind_direction, 245.0);
+    }
+
+    #[tokio::test]
+    async fn test_current_weather_precipitation_from_fixture() {
+        let mut server = serve_fixture("/data/2.5/forecast", FORECAST_FIXTURE).await;
+        server
+            .mock("GET", mockito::Matcher::Regex("^/data/2.5/weather".to_string()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(CURRENT_FIXTURE)
+            .create_async()
+            .await;
+        let provider = OpenWeatherProvider::with_base_url("test_key", &server.url());
+
+        let weather = provider.get_current_weather(51.5, -0.12).await.unwrap();
+
+        assert_eq!(weather.condition, "Rain");
+        assert_eq!(weather.condition_code, ConditionCode::LightRain);
+        assert_eq!(weather.is_day, Some(true));
+        assert_eq!(weather.precipitation_mm, Some(0.42));
+        // Highest pop among the first eight 3-hour entries
+        assert!((weather.precipitation_probability.unwrap() - 60.0).abs() < 1e-9);
+        assert_eq!(weather.visibility, Some(9000.0));
+        assert_eq!(weather.pressure, Some(1012.0));
+        assert!((weather.wind_speed - 4.6).abs() < 1e-6);
+        assert_eq!(weather.uv_index, None);
+        // The fixture's forecast is for a day long gone, so today's range is unknown
+        // rather than the fixture's area-wide temp_max and temp_min
+        assert_eq!(weather.max_temp, None);
+        assert_eq!(weather.min_temp, None);
+        let locality = weather.locality.unwrap();
+        assert_eq!((locality.name.as_str(), locality.country.as_str()), ("London", "GB"));
+        assert_eq!(locality.timezone, None);
+    }
 
-        result.sort_by(|a, b| a.date.cmp(&b.date));
-        Ok(result)
+    #[tokio::test]
+    async fn test_current_weather_reuses_forecast() {
+        let mut server = mockito::Server::new_async().await;
+        let forecast = server
+            .mock("GET", mockito::Matcher::Regex("^/data/2.5/forecast".to_string()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(FORECAST_FIXTURE)
+            .expect(1)
+            .create_async()
+            .await;
+        let current = server
+            .mock("GET", mockito::Matcher::Regex("^/data/2.5/weather".to_string()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(CURRENT_FIXTURE)
+            .expect(2)
+            .create_async()
+            .await;
+        let provider = OpenWeatherProvider::with_base_url("test_key", &server.url());
+
+        provider.get_current_weather(51.5, -0.12).await.unwrap();
+        let weather = provider.get_current_weather(51.501, -0.12).await.unwrap();
+
+        forecast.assert_async().await;
+        current.assert_async().await;
+        assert!((weather.precipitation_probability.unwrap() - 60.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_current_weather_without_cache_fetches_forecast_each_time() {
+        let mut server = mockito::Server::new_async().await;
+        let forecast = server
+            .mock("GET", mockito::Matcher::Regex("^/data/2.5/forecast".to_string()))
+            .with_status(200)
+            .with_body(FORECAST_FIXTURE)
+            .expect(2)
+            .create_async()
+            .await;
+        server
+            .mock("GET", mockito::Matcher::Regex("^/data/2.5/weather".to_string()))
+            .with_status(200)
+            .with_body(CURRENT_FIXTURE)
+            .create_async()
+            .await;
+        let cache = CacheConfig { enabled: false, ..Default::default() };
+        let provider = OpenWeatherProvider::with_base_url("test_key", &server.url()).with_cache(&cache);
+
+        provider.get_current_weather(51.5, -0.12).await.unwrap();
+        provider.get_current_weather(51.5, -0.12).await.unwrap();
+
+        forecast.assert_async().await;
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_today_range_only_from_today() {
+        let forecast: OpenWeatherForecastResponse = serde_json::from_str(FORECAST_FIXTURE).unwrap();
+        let outlook = Outlook::from_forecast(&forecast);
+        // 2024-03-20T00:00:00Z, the fixture's first entry
+        let first_slot = 1710892800;
+
+        // Late on the 19th the first entry is already tomorrow
+        assert_eq!(outlook.today_range(first_slot - 3600, 9.0), None);
+
+        // The 20th's entries run from 7.4 to 14.6
+        let (max, min) = outlook.today_range(first_slot + 10 * 3600, 10.0).unwrap();
+        assert!((max - 14.6).abs() < 1e-4);
+        assert!((min - 7.4).abs() < 1e-4);
+
+        // The current reading widens the range
+        let (max, min) = outlook.today_range(first_slot + 10 * 3600, 16.0).unwrap();
+        assert!((max - 16.0).abs() < 1e-9);
+        assert!((min - 7.4).abs() < 1e-4);
+    }
+
+    #[tokio::test]
+    async fn test_current_weather_with_rejected_key() {
+        let mut server = serve_fixture("/data/2.5/forecast", FORECAST_FIXTURE).await;
+        server
+            .mock("GET", mockito::Matcher::Regex("^/data/2.5/weather".to_string()))
+            .with_status(429)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"cod":429,"message":"Your account is temporary blocked due to exceeding of requests limitation."}"#)
+            .create_async()
+            .await;
+        let provider = OpenWeatherProvider::with_base_url("test_key", &server.url());
+
+        let error = provider.get_current_weather(51.5, -0.12).await.unwrap_err().to_string();
+        assert!(error.contains("429"), "unexpected error: {}", error);
+    }
+
+    #[tokio::test]
+    async fn test_forecast_with_rejected_key() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", mockito::Matcher::Regex("^/data/2.5/forecast".to_string()))
+            .with_status(401)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"cod":401,"message":"Invalid API key."}"#)
+            .create_async()
+            .await;
+        let provider = OpenWeatherProvider::with_base_url("bad_key", &server.url());
+
+        let error = provider.get_forecast(51.5, -0.12, 3).await.unwrap_err().to_string();
+        assert!(error.contains("401"), "unexpected error: {}", error);
+        assert!(!error.contains("missing field"), "unexpected error: {}", error);
+    }
+
+    #[tokio::test]
+    async fn test_forecast_precipitation_from_fixture() {
+        let server = serve_fixture("/data/2.5/forecast", FORECAST_FIXTURE).await;
+        let provider = OpenWeatherProvider::with_base_url("test_key", &server.url());
+
+        let forecast = provider.get_forecast(51.5, -0.12, 3).await.unwrap();
+
+        let first = forecast.iter().find(|d| d.date == "2024-03-20").unwrap();
+        assert_eq!(first.locality.as_ref().map(|l| l.name.as_str()), Some("London"));
+        assert!((first.precipitation_probability.unwrap() - 60.0).abs() < 1e-4);
+        assert!((first.precipitation_mm.unwrap() - 1.1).abs() < 1e-4);
+        let second = forecast.iter().find(|d| d.date == "2024-03-21").unwrap();
+        assert_eq!(second.precipitation_mm, Some(0.0));
+    }
+
+    #[tokio::test]
+    async fn test_forecast_days_are_chronological_from_fixture() {
+        let server = serve_fixture("/data/2.5/forecast", FORECAST_FIXTURE).await;
+        let provider = OpenWeatherProvider::with_base_url("test_key", &server.url());
+
+        let forecast = provider.get_forecast(51.5, -0.12, 2).await.unwrap();
+
+        let dates: Vec<&str> = forecast.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(dates, ["2024-03-20", "2024-03-21"]);
+        // Four of the eight entries on the 21st are scattered clouds
+        assert_eq!(forecast[1].condition_code, ConditionCode::PartlyCloudy);
+        assert_eq!(forecast[1].condition, "Clouds");
+    }
+
+    #[tokio::test]
+    async fn test_forecast_days_follow_city_timezone_from_fixture() {
+        // Same entries, but for a city five hours behind UTC
+        let shifted = FORECAST_FIXTURE.replace("\"timezone\": 0", "\"timezone\": -18000");
+        let server = serve_fixture("/data/2.5/forecast", &shifted).await;
+        let provider = OpenWeatherProvider::with_base_url("test_key", &server.url());
+
+        let forecast = provider.get_forecast(40.7, -74.0, 2).await.unwrap();
+
+        assert_eq!(forecast[0].date, "2024-03-19");
+        // Only the 00:00 and 03:00 UTC entries fall on the local 19th
+        assert_eq!(forecast[0].condition_code, ConditionCode::Clear);
+        assert!((forecast[0].max_temp.unwrap() - 8.1).abs() < 1e-4);
+        assert_eq!(forecast[1].date, "2024-03-20");
+        assert_eq!(forecast[1].condition_code, ConditionCode::PartlyCloudy);
+        assert!((forecast[1].precipitation_mm.unwrap() - 1.1).abs() < 1e-4);
+    }
+
+    #[tokio::test]
+    async fn test_get_air_quality_from_fixture() {
+        let server = serve_fixture("/data/2.5/air_pollution", AIR_POLLUTION_FIXTURE).await;
+        let provider = OpenWeatherProvider::with_base_url("test_key", &server.url());
+
+        let air = provider.get_air_quality(51.5, -0.12).await.unwrap();
+
+        assert_eq!(air.pm2_5, Some(7.41));
+        assert_eq!(air.pm10, Some(9.83));
+        assert_eq!(air.co, Some(230.31));
+        assert_eq!(air.european_index(), Some(2));
+        assert_eq!(air.us_epa_index(), Some(41));
+    }
+
+    #[tokio::test]
+    async fn test_air_quality_with_rejected_key() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", mockito::Matcher::Regex("^/data/2.5/air_pollution".to_string()))
+            .with_status(401)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"cod":401,"message":"Invalid API key."}"#)
+            .create_async()
+            .await;
+        let provider = OpenWeatherProvider::with_base_url("bad_key", &server.url());
+
+        let error = provider.get_air_quality(51.5, -0.12).await.unwrap_err().to_string();
+        assert!(error.contains("401"), "unexpected error: {}", error);
+    }
+}
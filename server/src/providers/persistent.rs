@@ -0,0 +1,238 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use crate::config::StorageConfig;
+use crate::providers::traits::{
+    WeatherProvider, AirQuality, CurrentWeather, DayForecast, HistoricalDay, HourlyForecast, ProviderCapabilities,
+    WeatherAlert,
+};
+use crate::providers::dates;
+use crate::storage::{unix_now, WeatherStore};
+use tracing::{debug, warn};
+
+/// Writes every successful response of `inner` to the SQLite store and
+/// answers from it while the stored copy is still fresh, so a restarted
+/// server does not have to wait for the upstream API.
+pub struct PersistentProvider {
+    inner: Arc<dyn WeatherProvider>,
+    store: Arc<WeatherStore>,
+    current_fresh_ttl_secs: i64,
+    forecast_fresh_ttl_secs: i64,
+}
+
+impl PersistentProvider {
+    pub fn new(inner: Arc<dyn WeatherProvider>, store: Arc<WeatherStore>, config: &StorageConfig) -> Self {
+        Self {
+            inner,
+            store,
+            current_fresh_ttl_secs: config.current_fresh_ttl_secs as i64,
+            forecast_fresh_ttl_secs: config.forecast_fresh_ttl_secs as i64,
+        }
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for PersistentProvider {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn get_current_weather(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<CurrentWeather, Box<dyn std::error::Error + Send + Sync>> {
+        let name = self.inner.name();
+        // A broken store must never fail the request, only cost a provider call
+        match self.store.get_current(&name, latitude, longitude).await {
+            Ok(Some(stored)) if stored.age_secs() < self.current_fresh_ttl_secs => {
+                debug!(provider = %name, age_secs = stored.age_secs(), "Serving stored current weather");
+                return Ok(stored.value);
+            }
+            Ok(_) => {}
+            Err(e) => warn!(provider = %name, error = %e, "Failed to read stored current weather"),
+        }
+
+        let weather = self.inner.get_current_weather(latitude, longitude).await?;
+        if let Err(e) = self.store.put_current(&name, latitude, longitude, &weather).await {
+            warn!(provider = %name, error = %e, "Failed to store current weather");
+        }
+        Ok(weather)
+    }
+
+    async fn get_forecast(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        days: i32,
+    ) -> Result<Vec<DayForecast>, Box<dyn std::error::Error + Send + Sync>> {
+        let name = self.inner.name();
+        match self.store.get_forecast(&name, latitude, longitude, days).await {
+            Ok(Some(stored)) if stored.age_secs() < self.forecast_fresh_ttl_secs => {
+                debug!(provider = %name, age_secs = stored.age_secs(), "Serving stored forecast");
+                return Ok(stored.value);
+            }
+            Ok(_) => {}
+            Err(e) => warn!(provider = %name, error = %e, "Failed to read stored forecast"),
+        }
+
+        let forecast = self.inner.get_forecast(latitude, longitude, days).await?;
+        if let Err(e) = self.store.put_forecast(&name, latitude, longitude, days, &forecast).await {
+            warn!(provider = %name, error = %e, "Failed to store forecast");
+        }
+        Ok(forecast)
+    }
+
+    async fn get_hourly_forecast(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        hours: i32,
+    ) -> Result<Vec<HourlyForecast>, Box<dyn std::error::Error + Send + Sync>> {
+        // Hourly slots go out of date within the hour, so they are not persisted
+        self.inner.get_hourly_forecast(latitude, longitude, hours).await
+    }
+
+    async fn get_alerts(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Vec<WeatherAlert>, Box<dyn std::error::Error + Send + Sync>> {
+        // Serving an alert that has since been lifted would be worse than none
+        self.inner.get_alerts(latitude, longitude).await
+    }
+
+    async fn get_air_quality(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<AirQuality, Box<dyn std::error::Error + Send + Sync>> {
+        // Not kept in the store; the cache in front absorbs repeat lookups
+        self.inner.get_air_quality(latitude, longitude).await
+    }
+
+    async fn get_historical(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        date: &str,
+    ) -> Result<HistoricalDay, Box<dyn std::error::Error + Send + Sync>> {
+        // Past days never change, so a stored day is served however old it is
+        let name = self.inner.name();
+        match self.store.get_historical(&name, latitude, longitude, date).await {
+            Ok(Some(stored)) => {
+                debug!(provider = %name, date, "Serving stored history");
+                return Ok(stored.value);
+            }
+            Ok(None) => {}
+            Err(e) => warn!(provider = %name, error = %e, "Failed to read stored history"),
+        }
+
+        let day = self.inner.get_historical(latitude, longitude, date).await?;
+        // A day still under way somewhere may yet be revised
+        if dates::has_ended_everywhere(date, unix_now()) {
+            if let Err(e) = self.store.put_historical(&name, latitude, longitude, &day).await {
+                warn!(provider = %name, error = %e, "Failed to store history");
+            }
+        }
+        Ok(day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CacheConfig;
+    use crate::tests::support::StubProvider;
+
+    fn config(current_fresh_ttl_secs: u64, forecast_fresh_ttl_secs: u64) -> StorageConfig {
+        StorageConfig {
+            current_fresh_ttl_secs,
+            forecast_fresh_ttl_secs,
+            ..Default::default()
+        }
+    }
+
+    async fn temp_store(dir: &tempfile::TempDir) -> Arc<WeatherStore> {
+        let url = format!("sqlite://{}", dir.path().join("weather.db").display());
+        Arc::new(WeatherStore::connect(&url, 2).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_fresh_stored_response_skips_provider() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = temp_store(&dir).await;
+        let stub = Arc::new(StubProvider::new("stub").with_temperature(7.0));
+
+        let first = PersistentProvider::new(stub.clone(), store.clone(), &config(600, 600));
+        first.get_current_weather(1.0, 2.0).await.unwrap();
+        first.get_forecast(1.0, 2.0, 2).await.unwrap();
+
+        // A new wrapper over the same database stands in for a restarted server
+        let restarted = PersistentProvider::new(stub.clone(), store, &config(600, 600));
+        assert_eq!(restarted.get_current_weather(1.0, 2.0).await.unwrap().temperature, 7.0);
+        assert_eq!(restarted.get_forecast(1.0, 2.0, 2).await.unwrap().len(), 2);
+        assert_eq!(stub.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expired_stored_response_refetches() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = temp_store(&dir).await;
+        let stub = Arc::new(StubProvider::new("stub"));
+        let provider = PersistentProvider::new(stub.clone(), store, &config(0, 0));
+
+        provider.get_current_weather(1.0, 2.0).await.unwrap();
+        provider.get_current_weather(1.0, 2.0).await.unwrap();
+        assert_eq!(stub.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_current_and_forecast_have_their_own_fresh_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = temp_store(&dir).await;
+        let stub = Arc::new(StubProvider::new("stub"));
+        let provider = PersistentProvider::new(stub.clone(), store, &config(0, 600));
+
+        provider.get_current_weather(1.0, 2.0).await.unwrap();
+        provider.get_current_weather(1.0, 2.0).await.unwrap();
+        assert_eq!(stub.calls(), 2);
+
+        provider.get_forecast(1.0, 2.0, 2).await.unwrap();
+        provider.get_forecast(1.0, 2.0, 2).await.unwrap();
+        assert_eq!(stub.calls(), 3);
+    }
+
+    #[test]
+    fn test_fresh_ttls_capped_by_cache() {
+        let cache = CacheConfig::default();
+        let storage = config(600, 600).capped_by(&cache);
+        assert_eq!(storage.current_fresh_ttl_secs, cache.current_ttl_secs);
+        assert_eq!(storage.forecast_fresh_ttl_secs, 600);
+
+        let uncached = CacheConfig { enabled: false, ..cache };
+        assert_eq!(config(600, 600).capped_by(&uncached).current_fresh_ttl_secs, 600);
+    }
+
+    #[tokio::test]
+    async fn test_past_days_are_kept_for_good() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = temp_store(&dir).await;
+        let stub = Arc::new(StubProvider::new("stub").with_history());
+        let provider = PersistentProvider::new(stub.clone(), store.clone(), &config(0, 0));
+
+        provider.get_historical(1.0, 2.0, "2024-03-10").await.unwrap();
+        let restarted = PersistentProvider::new(stub.clone(), store, &config(0, 0));
+        assert_eq!(restarted.get_historical(1.0, 2.0, "2024-03-10").await.unwrap().date, "2024-03-10");
+        assert_eq!(stub.calls(), 1);
+
+        // Today has not ended yet, so it is fetched every time
+        let today = dates::local_date(unix_now(), 0);
+        provider.get_historical(1.0, 2.0, &today).await.unwrap();
+        provider.get_historical(1.0, 2.0, &today).await.unwrap();
+        assert_eq!(stub.calls(), 3);
+    }
+}
@@ -0,0 +1,373 @@
+use std::sync::Arc;
+use std::time::Duration;
+use crate::config::{CacheConfig, ProvidersConfig};
+use crate::error::{AppError, AppResult};
+use crate::providers::{OpenMeteoProvider, OpenWeatherProvider, ProviderCapabilities, WeatherApiProvider, WeatherProvider};
+use tracing::info;
+
+/// A provider registered under a stable id, plus the names clients may
+/// use to select it.
+#[derive(Clone)]
+pub struct ProviderEntry {
+    pub id: String,
+    pub display_name: String,
+    pub aliases: Vec<String>,
+    pub enabled: bool,
+    pub provider: Arc<dyn WeatherProvider>,
+}
+
+impl ProviderEntry {
+    fn matches(&self, name: &str) -> bool {
+        self.id.eq_ignore_ascii_case(name)
+            || self.aliases.iter().any(|alias| alias.eq_ignore_ascii_case(name))
+    }
+}
+
+const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone)]
+pub struct ProviderRegistry {
+    entries: Vec<ProviderEntry>,
+    fallback_chain: Vec<String>,
+    call_timeout: Duration,
+}
+
+impl Default for ProviderRegistry {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            fallback_chain: Vec::new(),
+            call_timeout: DEFAULT_CALL_TIMEOUT,
+        }
+    }
+}
+
+impl ProviderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds the registry of built-in providers, enabling the ones listed
+    /// in `providers.enabled`. `cache` governs what providers keep
+    /// internally, such as the forecast OpenWeather needs for current weather.
+    pub fn from_settings(config: &ProvidersConfig, cache: &CacheConfig) -> AppResult<Self> {
+        let is_enabled = |id: &str| config.enabled.iter().any(|e| e.eq_ignore_ascii_case(id));
+
+        let mut registry = Self::new();
+        registry.register(ProviderEntry {
+            id: "openweather".to_string(),
+            display_name: "OpenWeather".to_string(),
+            aliases: vec!["openweathermap".to_string(), "owm".to_string()],
+            enabled: is_enabled("openweather"),
+            provider: Arc::new(OpenWeatherProvider::new(config.openweather_api_key.clone()).with_cache(cache)),
+        })?;
+        registry.register(ProviderEntry {
+            id: "weatherapi".to_string(),
+            display_name: "WeatherAPI".to_string(),
+            aliases: vec!["weatherapi.com".to_string()],
+            enabled: is_enabled("weatherapi"),
+            provider: Arc::new(WeatherApiProvider::new(config.weatherapi_api_key.clone())),
+        })?;
+        registry.register(ProviderEntry {
+            id: "openmeteo".to_string(),
+            display_name: "Open-Meteo".to_string(),
+            aliases: vec!["open-meteo".to_string()],
+            enabled: is_enabled("openmeteo"),
+            provider: Arc::new(OpenMeteoProvider::new()),
+        })?;
+
+        if let Some(unknown) = config.enabled.iter().find(|id| !registry.entries().any(|e| e.matches(id))) {
+            return Err(AppError::Config(format!("Unknown provider in providers.enabled: {}", unknown)));
+        }
+        registry.set_fallback_chain(config.fallback_chain.clone())?;
+        registry.set_call_timeout(Duration::from_millis(config.timeout_ms));
+
+        Ok(registry)
+    }
+
+    /// Adds a provider. Ids and aliases share one case-insensitive namespace.
+    pub fn register(&mut self, entry: ProviderEntry) -> AppResult<()> {
+        let names = std::iter::once(&entry.id).chain(entry.aliases.iter());
+        for name in names {
+            if let Some(existing) = self.entries.iter().find(|e| e.matches(name)) {
+                return Err(AppError::Config(format!(
+                    "Provider name '{}' is already registered by '{}'",
+                    name, existing.id
+                )));
+            }
+        }
+
+        info!(id = %entry.id, enabled = entry.enabled, "Registered weather provider");
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Looks up an enabled provider by id or alias.
+    pub fn get(&self, name: &str) -> AppResult<Arc<dyn WeatherProvider>> {
+        self.resolve(name).map(|entry| entry.provider.clone())
+    }
+
+    fn resolve(&self, name: &str) -> AppResult<&ProviderEntry> {
+        match self.entries.iter().find(|e| e.matches(name)) {
+            Some(entry) if entry.enabled => Ok(entry),
+            Some(entry) => Err(AppError::Provider(format!("Provider is disabled: {}", entry.id))),
+            None => Err(AppError::Invalidreqwest(format!("Invalid provider: {}", name))),
+        }
+    }
+
+    /// Sets the ids tried, in order, when a request names no provider or the
+    /// named one fails.
+    pub fn set_fallback_chain(&mut self, chain: Vec<String>) -> AppResult<()> {
+        let mut ids = Vec::with_capacity(chain.len());
+        for name in &chain {
+            let entry = self.entries.iter().find(|e| e.matches(name)).ok_or_else(|| {
+                AppError::Config(format!("Unknown provider in providers.fallback_chain: {}", name))
+            })?;
+            if !ids.contains(&entry.id) {
+                ids.push(entry.id.clone());
+            }
+        }
+
+        self.fallback_chain = ids;
+        Ok(())
+    }
+
+    pub fn set_call_timeout(&mut self, timeout: Duration) {
+        self.call_timeout = timeout;
+    }
+
+    /// Upper bound for a single provider call before moving down the chain.
+    pub fn call_timeout(&self) -> Duration {
+        self.call_timeout
+    }
+
+    /// Providers to try for a request, in order: the requested one first (if
+    /// any), then the enabled members of the fallback chain.
+    pub fn candidates(&self, requested: &str) -> AppResult<Vec<Arc<dyn WeatherProvider>>> {
+        let mut chosen: Vec<&ProviderEntry> = Vec::new();
+        if !requested.is_empty() {
+            chosen.push(self.resolve(requested)?);
+        }
+        // Disabled members of the chain are skipped rather than failing the request
+        for entry in self.fallback_chain.iter().filter_map(|id| self.resolve(id).ok()) {
+            if !chosen.iter().any(|c| c.id == entry.id) {
+                chosen.push(entry);
+            }
+        }
+
+        if chosen.is_empty() {
+            return Err(AppError::Invalidreqwest(
+                "No provider requested and no enabled fallback provider configured".to_string(),
+            ));
+        }
+        Ok(chosen.into_iter().map(|entry| entry.provider.clone()).collect())
+    }
+
+    /// Like `candidates`, but only providers whose capabilities offer
+    /// `feature`. Naming a provider without it is an error rather than a
+    /// silent fallback to one that has it.
+    pub fn candidates_with(
+        &self,
+        requested: &str,
+        feature: &str,
+        offers: fn(&ProviderCapabilities) -> bool,
+    ) -> AppResult<Vec<Arc<dyn WeatherProvider>>> {
+        if !requested.is_empty() {
+            let entry = self.resolve(requested)?;
+            if !offers(&entry.provider.capabilities()) {
+                return Err(AppError::Unsupported(format!("Provider {} does not provide {}", entry.id, feature)));
+            }
+        }
+
+        let candidates: Vec<_> = self
+            .candidates(requested)?
+            .into_iter()
+            .filter(|provider| offers(&provider.capabilities()))
+            .collect();
+        if candidates.is_empty() {
+            return Err(AppError::Unsupported(format!("No enabled provider provides {}", feature)));
+        }
+        Ok(candidates)
+    }
+
+    /// Replaces every registered provider with `layer(provider)`, e.g. to put
+    /// a cache in front of all of them.
+    pub fn wrap_providers<F>(&mut self, layer: F)
+    where
+        F: Fn(Arc<dyn WeatherProvider>) -> Arc<dyn WeatherProvider>,
+    {
+        for entry in &mut self.entries {
+            entry.provider = layer(entry.provider.clone());
+        }
+    }
+
+    /// Every enabled provider in registration order.
+    pub fn enabled(&self) -> Vec<Arc<dyn WeatherProvider>> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.enabled)
+            .map(|entry| entry.provider.clone())
+            .collect()
+    }
+
+    /// All registered providers in registration order, including disabled ones.
+    pub fn entries(&self) -> impl Iterator<Item = &ProviderEntry> {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::support::StubProvider;
+
+    fn entry(id: &str, aliases: &[&str], enabled: bool) -> ProviderEntry {
+        ProviderEntry {
+            id: id.to_string(),
+            display_name: id.to_uppercase(),
+            aliases: aliases.iter().map(|a| a.to_string()).collect(),
+            enabled,
+            provider: Arc::new(StubProvider::new(id)),
+        }
+    }
+
+    #[test]
+    fn test_get_by_id_and_alias() {
+        let mut registry = ProviderRegistry::new();
+        registry.register(entry("alpha", &["a"], true)).unwrap();
+
+        assert_eq!(registry.get("alpha").unwrap().name(), "alpha");
+        assert_eq!(registry.get("ALPHA").unwrap().name(), "alpha");
+        assert_eq!(registry.get("A").unwrap().name(), "alpha");
+    }
+
+    #[test]
+    fn test_get_unknown_provider() {
+        let registry = ProviderRegistry::new();
+
+        assert!(matches!(registry.get("nope"), Err(AppError::Invalidreqwest(_))));
+    }
+
+    #[test]
+    fn test_get_disabled_provider() {
+        let mut registry = ProviderRegistry::new();
+        registry.register(entry("alpha", &[], false)).unwrap();
+
+        assert!(matches!(registry.get("alpha"), Err(AppError::Provider(_))));
+        assert_eq!(registry.entries().count(), 1);
+        assert!(registry.enabled().is_empty());
+    }
+
+    #[test]
+    fn test_register_rejects_duplicate_names() {
+        let mut registry = ProviderRegistry::new();
+        registry.register(entry("alpha", &["shared"], true)).unwrap();
+
+        assert!(registry.register(entry("Alpha", &[], true)).is_err());
+        assert!(registry.register(entry("beta", &["SHARED"], true)).is_err());
+        assert!(registry.register(entry("beta", &[], true)).is_ok());
+    }
+
+    #[test]
+    fn test_from_settings_respects_enabled_list() {
+        let config = ProvidersConfig {
+            openweather_api_key: "ow_key".to_string(),
+            weatherapi_api_key: "wa_key".to_string(),
+            enabled: vec!["openmeteo".to_string()],
+            fallback_chain: vec!["openmeteo".to_string()],
+            timeout_ms: 500,
+        };
+        let registry = ProviderRegistry::from_settings(&config, &CacheConfig::default()).unwrap();
+
+        let ids: Vec<_> = registry.entries().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["openweather", "weatherapi", "openmeteo"]);
+        assert!(registry.get("openweather").is_err());
+        assert_eq!(registry.get("open-meteo").unwrap().name(), "OpenMeteo");
+        assert_eq!(registry.call_timeout(), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_from_settings_rejects_unknown_provider() {
+        let config = ProvidersConfig {
+            openweather_api_key: "ow_key".to_string(),
+            weatherapi_api_key: "wa_key".to_string(),
+            enabled: vec!["darksky".to_string()],
+            fallback_chain: Vec::new(),
+            timeout_ms: 500,
+        };
+
+        assert!(matches!(ProviderRegistry::from_settings(&config, &CacheConfig::default()), Err(AppError::Config(_))));
+    }
+
+    fn names(candidates: &[Arc<dyn WeatherProvider>]) -> Vec<String> {
+        candidates.iter().map(|p| p.name()).collect()
+    }
+
+    #[test]
+    fn test_candidates_follow_fallback_chain() {
+        let mut registry = ProviderRegistry::new();
+        registry.register(entry("alpha", &[], true)).unwrap();
+        registry.register(entry("beta", &["b"], true)).unwrap();
+        registry.register(entry("gamma", &[], false)).unwrap();
+        registry.set_fallback_chain(vec!["gamma".to_string(), "alpha".to_string(), "beta".to_string()]).unwrap();
+
+        assert_eq!(names(&registry.candidates("").unwrap()), ["alpha", "beta"]);
+        assert_eq!(names(&registry.candidates("B").unwrap()), ["beta", "alpha"]);
+        assert!(registry.candidates("gamma").is_err());
+        assert!(registry.candidates("delta").is_err());
+    }
+
+    #[test]
+    fn test_candidates_without_chain() {
+        let mut registry = ProviderRegistry::new();
+        registry.register(entry("alpha", &[], true)).unwrap();
+
+        assert_eq!(names(&registry.candidates("alpha").unwrap()), ["alpha"]);
+        assert!(matches!(registry.candidates(""), Err(AppError::Invalidreqwest(_))));
+    }
+
+    #[test]
+    fn test_candidates_with_feature() {
+        let mut registry = ProviderRegistry::new();
+        registry.register(entry("alpha", &[], true)).unwrap();
+        registry.register(ProviderEntry {
+            provider: Arc::new(StubProvider::new("beta").with_alerts(Vec::new())),
+            ..entry("beta", &[], true)
+        }).unwrap();
+        registry.set_fallback_chain(vec!["alpha".to_string(), "beta".to_string()]).unwrap();
+        let alerts = |c: &ProviderCapabilities| c.supports_alerts;
+
+        assert_eq!(names(&registry.candidates_with("", "alerts", alerts).unwrap()), ["beta"]);
+        assert_eq!(names(&registry.candidates_with("beta", "alerts", alerts).unwrap()), ["beta"]);
+        assert!(matches!(
+            registry.candidates_with("alpha", "alerts", alerts),
+            Err(AppError::Unsupported(_))
+        ));
+        assert!(matches!(
+            registry.candidates_with("", "historical data", |_| false),
+            Err(AppError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_set_fallback_chain_rejects_unknown_provider() {
+        let mut registry = ProviderRegistry::new();
+        registry.register(entry("alpha", &[], true)).unwrap();
+
+        assert!(matches!(
+            registry.set_fallback_chain(vec!["alpha".to_string(), "delta".to_string()]),
+            Err(AppError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_wrap_providers_replaces_every_provider() {
+        let mut registry = ProviderRegistry::new();
+        registry.register(entry("alpha", &[], true)).unwrap();
+        registry.register(entry("beta", &[], false)).unwrap();
+
+        registry.wrap_providers(|_| Arc::new(StubProvider::new("wrapped")));
+
+        assert!(registry.entries().all(|e| e.provider.name() == "wrapped"));
+    }
+}
\ No newline at end of file
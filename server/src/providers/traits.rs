@@ -1,31 +1,339 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use crate::geocoding::Locality;
 
+/// Temperature units. Canonical: degrees Celsius.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+impl TemperatureUnit {
+    pub fn to_canonical(self, value: f64) -> f64 {
+        match self {
+            TemperatureUnit::Celsius => value,
+            TemperatureUnit::Fahrenheit => (value - 32.0) / 1.8,
+            TemperatureUnit::Kelvin => value - 273.15,
+        }
+    }
+
+    pub fn from_canonical(self, celsius: f64) -> f64 {
+        match self {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => celsius * 1.8 + 32.0,
+            TemperatureUnit::Kelvin => celsius + 273.15,
+        }
+    }
+
+    /// Converts a temperature difference, which unlike a reading has no offset.
+    pub fn delta_from_canonical(self, celsius: f64) -> f64 {
+        match self {
+            TemperatureUnit::Fahrenheit => celsius * 1.8,
+            TemperatureUnit::Celsius | TemperatureUnit::Kelvin => celsius,
+        }
+    }
+}
+
+/// Speed units. Canonical: metres per second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeedUnit {
+    MetersPerSecond,
+    KilometersPerHour,
+    MilesPerHour,
+    Knots,
+}
+
+impl SpeedUnit {
+    fn meters_per_second(self) -> f64 {
+        match self {
+            SpeedUnit::MetersPerSecond => 1.0,
+            SpeedUnit::KilometersPerHour => 1000.0 / 3600.0,
+            SpeedUnit::MilesPerHour => 1609.344 / 3600.0,
+            SpeedUnit::Knots => 1852.0 / 3600.0,
+        }
+    }
+
+    pub fn to_canonical(self, value: f64) -> f64 {
+        value * self.meters_per_second()
+    }
+
+    pub fn from_canonical(self, meters_per_second: f64) -> f64 {
+        meters_per_second / self.meters_per_second()
+    }
+}
+
+/// Distance units. Canonical: metres.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceUnit {
+    Meters,
+    Kilometers,
+    Miles,
+}
+
+impl DistanceUnit {
+    fn meters(self) -> f64 {
+        match self {
+            DistanceUnit::Meters => 1.0,
+            DistanceUnit::Kilometers => 1000.0,
+            DistanceUnit::Miles => 1609.344,
+        }
+    }
+
+    pub fn to_canonical(self, value: f64) -> f64 {
+        value * self.meters()
+    }
+
+    pub fn from_canonical(self, meters: f64) -> f64 {
+        meters / self.meters()
+    }
+}
+
+/// Pressure units. Canonical: hectopascals (millibars).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PressureUnit {
+    Hectopascals,
+    InchesOfMercury,
+    MillimetersOfMercury,
+}
+
+impl PressureUnit {
+    fn hectopascals(self) -> f64 {
+        match self {
+            PressureUnit::Hectopascals => 1.0,
+            PressureUnit::InchesOfMercury => 33.863_886,
+            PressureUnit::MillimetersOfMercury => 1.333_224,
+        }
+    }
+
+    pub fn to_canonical(self, value: f64) -> f64 {
+        value * self.hectopascals()
+    }
+
+    pub fn from_canonical(self, hectopascals: f64) -> f64 {
+        hectopascals / self.hectopascals()
+    }
+}
+
+/// Units a client wants values reported in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Units {
+    pub temperature: TemperatureUnit,
+    pub speed: SpeedUnit,
+    pub distance: DistanceUnit,
+    pub pressure: PressureUnit,
+}
+
+impl Units {
+    pub const METRIC: Units = Units {
+        temperature: TemperatureUnit::Celsius,
+        speed: SpeedUnit::KilometersPerHour,
+        distance: DistanceUnit::Kilometers,
+        pressure: PressureUnit::Hectopascals,
+    };
+
+    pub const IMPERIAL: Units = Units {
+        temperature: TemperatureUnit::Fahrenheit,
+        speed: SpeedUnit::MilesPerHour,
+        distance: DistanceUnit::Miles,
+        pressure: PressureUnit::InchesOfMercury,
+    };
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Units::METRIC
+    }
+}
+
+/// Provider-independent weather condition. Each provider maps its native
+/// codes onto these; the provider's own wording stays in `condition`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ConditionCode {
+    #[default]
+    Unknown,
+    Clear,
+    PartlyCloudy,
+    Cloudy,
+    Overcast,
+    Mist,
+    Fog,
+    Haze,
+    Smoke,
+    Dust,
+    Drizzle,
+    FreezingDrizzle,
+    LightRain,
+    Rain,
+    HeavyRain,
+    FreezingRain,
+    RainShowers,
+    Sleet,
+    LightSnow,
+    Snow,
+    HeavySnow,
+    SnowShowers,
+    IcePellets,
+    Thunderstorm,
+    Squall,
+    Tornado,
+}
+
+/// Current conditions in canonical units (see the unit types above);
+/// precipitation is always in millimetres. Fields not every provider
+/// reports are `None` when unknown, never a placeholder value.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CurrentWeather {
     pub temperature: f64,
     pub humidity: f64,
+    /// Human-readable description as worded by the provider.
     pub condition: String,
+    #[serde(default)]
+    pub condition_code: ConditionCode,
+    /// Whether the sun is up, for picking day or night icons.
+    #[serde(default)]
+    pub is_day: Option<bool>,
     pub wind_speed: f64,
     pub wind_direction: f64,
-    pub uv_index: f64,
-    pub visibility: f64,
+    pub uv_index: Option<f64>,
+    pub visibility: Option<f64>,
+    #[serde(default)]
+    pub pressure: Option<f64>,
     pub country: String,
-    pub max_temp: f64,
-    pub min_temp: f64,
+    /// Today's extremes.
+    pub max_temp: Option<f64>,
+    pub min_temp: Option<f64>,
+    /// Chance of precipitation over the coming day, in percent.
+    #[serde(default)]
+    pub precipitation_probability: Option<f64>,
+    /// Precipitation over the last hour, in millimetres.
+    #[serde(default)]
+    pub precipitation_mm: Option<f64>,
+    /// Place the provider resolved the coordinates to, when its payload names one.
+    #[serde(default)]
+    pub locality: Option<Locality>,
 }
 
+/// One day of forecast, temperatures in degrees Celsius.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DayForecast {
     pub date: String,
-    pub max_temp: f32,
-    pub min_temp: f32,
+    /// Highest and lowest temperature of the day, `None` if unknown.
+    pub max_temp: Option<f32>,
+    pub min_temp: Option<f32>,
     pub condition: String,
+    #[serde(default)]
+    pub condition_code: ConditionCode,
+    /// Chance of precipitation during the day, in percent.
+    #[serde(default)]
+    pub precipitation_probability: Option<f32>,
+    /// Total precipitation expected during the day, in millimetres.
+    #[serde(default)]
+    pub precipitation_mm: Option<f32>,
+    /// Place the provider resolved the coordinates to, when its payload
+    /// names one; the same on every day of a forecast.
+    #[serde(default)]
+    pub locality: Option<Locality>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HourlyForecast {
+    /// Unix seconds at the start of the period.
+    pub timestamp: i64,
+    pub temperature: f64,
+    /// Chance of precipitation in percent.
+    pub precipitation_probability: Option<f64>,
+    /// Wind speed in metres per second.
+    pub wind_speed: f64,
+    pub wind_direction: f64,
+    pub condition: String,
+    #[serde(default)]
+    pub condition_code: ConditionCode,
+    #[serde(default)]
+    pub is_day: Option<bool>,
+}
+
+/// Observed weather of one past day at a location, in canonical units.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoricalDay {
+    /// Local calendar date at the location, `YYYY-MM-DD`.
+    pub date: String,
+    pub max_temp: f64,
+    pub min_temp: f64,
+    pub avg_temp: Option<f64>,
+    pub condition: String,
+    pub condition_code: ConditionCode,
+    /// Total precipitation over the day, in millimetres.
+    pub precipitation_mm: Option<f64>,
+    /// Strongest wind of the day, in metres per second.
+    pub max_wind_speed: Option<f64>,
+    pub avg_humidity: Option<f64>,
+}
+
+/// Alert severity on the Common Alerting Protocol scale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AlertSeverity {
+    #[default]
+    Unknown,
+    Minor,
+    Moderate,
+    Severe,
+    Extreme,
+}
+
+/// An active severe weather alert issued for a location.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeatherAlert {
+    pub event: String,
+    pub severity: AlertSeverity,
+    pub headline: String,
+    pub description: String,
+    /// Issuing agency, when the feed names one.
+    pub agency: Option<String>,
+    /// Unix seconds; `None` when the feed leaves it open.
+    pub starts_at: Option<i64>,
+    pub ends_at: Option<i64>,
+}
+
+/// Pollutant concentrations near the ground, all in µg/m³. Pollutants the
+/// provider does not report are `None`; indices are derived from these.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AirQuality {
+    pub pm2_5: Option<f64>,
+    pub pm10: Option<f64>,
+    pub o3: Option<f64>,
+    pub no2: Option<f64>,
+    pub so2: Option<f64>,
+    pub co: Option<f64>,
+}
+
+/// What a provider can serve, so clients only offer what it actually returns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderCapabilities {
+    pub max_forecast_days: i32,
+    pub max_forecast_hours: i32,
+    /// `WeatherResponse` field names filled with real data rather than defaults.
+    pub supported_fields: Vec<&'static str>,
+    pub supports_alerts: bool,
+    pub supports_air_quality: bool,
+    pub supports_historical: bool,
+}
+
+/// Returned when asked for something the provider's capabilities rule out,
+/// as opposed to a call that failed.
+#[derive(Debug, thiserror::Error)]
+#[error("{provider} does not provide {feature}")]
+pub struct Unsupported {
+    pub provider: String,
+    pub feature: &'static str,
 }
 
 #[async_trait]
 pub trait WeatherProvider: Send + Sync {
     fn name(&self) -> String;
+
+    fn capabilities(&self) -> ProviderCapabilities;
     
     async fn get_current_weather(
         &self,
@@ -39,4 +347,72 @@ pub trait WeatherProvider: Send + Sync {
         longitude: f64,
         days: i32,
     ) -> Result<Vec<DayForecast>, Box<dyn std::error::Error + Send + Sync>>;
-}
\ No newline at end of file
+
+    /// Forecast periods starting at the current hour, covering `hours` hours.
+    /// Providers with coarser steps (e.g. 3-hourly) return fewer entries.
+    async fn get_hourly_forecast(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        hours: i32,
+    ) -> Result<Vec<HourlyForecast>, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Alerts currently in effect. Providers without an alert feed return
+    /// `Unsupported` and leave `supports_alerts` unset.
+    async fn get_alerts(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Vec<WeatherAlert>, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Current air quality. Providers without air quality data return
+    /// `Unsupported` and leave `supports_air_quality` unset.
+    async fn get_air_quality(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<AirQuality, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Observed weather on a past `date` (`YYYY-MM-DD`, local to the
+    /// location). Providers with an archive override this and set
+    /// `supports_historical`.
+    async fn get_historical(
+        &self,
+        _latitude: f64,
+        _longitude: f64,
+        _date: &str,
+    ) -> Result<HistoricalDay, Box<dyn std::error::Error + Send + Sync>> {
+        Err(Box::new(Unsupported {
+            provider: self.name(),
+            feature: "historical weather",
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn test_temperature_conversions() {
+        assert!(close(TemperatureUnit::Fahrenheit.from_canonical(100.0), 212.0));
+        assert!(close(TemperatureUnit::Fahrenheit.to_canonical(-40.0), -40.0));
+        assert!(close(TemperatureUnit::Kelvin.from_canonical(0.0), 273.15));
+        assert!(close(TemperatureUnit::Fahrenheit.delta_from_canonical(10.0), 18.0));
+        assert!(close(TemperatureUnit::Kelvin.delta_from_canonical(10.0), 10.0));
+    }
+
+    #[test]
+    fn test_linear_conversions_round_trip() {
+        assert!(close(SpeedUnit::KilometersPerHour.to_canonical(36.0), 10.0));
+        assert!(close(SpeedUnit::MilesPerHour.from_canonical(SpeedUnit::MilesPerHour.to_canonical(12.3)), 12.3));
+        assert!(close(SpeedUnit::Knots.from_canonical(1852.0 / 3600.0), 1.0));
+        assert!(close(DistanceUnit::Kilometers.from_canonical(9000.0), 9.0));
+        assert!(close(DistanceUnit::Miles.to_canonical(1.0), 1609.344));
+        assert!(close(PressureUnit::InchesOfMercury.from_canonical(1013.25), 29.921_256));
+    }
+}
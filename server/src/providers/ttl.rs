@@ -0,0 +1,80 @@
+//! The TTL + LRU map behind every in-memory cache: provider responses,
+//! geocoder answers and the forecast OpenWeather completes current weather from.
+
+use std::hash::Hash;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::Duration;
+use lru::LruCache;
+use tokio::time::Instant;
+
+struct Entry<V> {
+    value: V,
+    expires_at: Instant,
+}
+
+/// Up to `capacity` entries, each served until its own TTL runs out; the
+/// least recently used goes first when full.
+pub(crate) struct TtlLru<K, V> {
+    entries: Mutex<LruCache<K, Entry<V>>>,
+}
+
+impl<K: Hash + Eq, V: Clone> TtlLru<K, V> {
+    /// A `capacity` of zero still keeps one entry.
+    pub(crate) fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// The live value under `key`, dropping it if it has expired.
+    pub(crate) fn get(&self, key: &K) -> Option<V> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some(entry) if entry.expires_at > Instant::now() => Some(entry.value.clone()),
+            Some(_) => {
+                entries.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub(crate) fn insert(&self, key: K, value: V, ttl: Duration) {
+        self.entries.lock().unwrap().put(key, Entry {
+            value,
+            expires_at: Instant::now() + ttl,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_entries_expire_by_their_own_ttl() {
+        let cache = TtlLru::new(10);
+        cache.insert("short", 1, Duration::from_secs(10));
+        cache.insert("long", 2, Duration::from_secs(60));
+
+        tokio::time::advance(Duration::from_secs(11)).await;
+        assert_eq!(cache.get(&"short"), None);
+        assert_eq!(cache.get(&"long"), Some(2));
+    }
+
+    #[test]
+    fn test_least_recently_used_evicted_when_full() {
+        let cache = TtlLru::new(2);
+        let ttl = Duration::from_secs(60);
+        cache.insert("a", 1, ttl);
+        cache.insert("b", 2, ttl);
+        cache.get(&"a");
+        cache.insert("c", 3, ttl);
+
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert_eq!(TtlLru::<&str, i32>::new(0).get(&"a"), None);
+    }
+}
@@ -1,58 +1,76 @@
 use async_trait::async_trait;
 use serde::Deserialize;
-use crate::providers::traits::{WeatherProvider, CurrentWeather, DayForecast};
-use crate::config::Settings;
-use once_cell::sync::Lazy;
+use crate::geocoding::Locality;
+use crate::providers::traits::{
+    WeatherProvider, AirQuality, AlertSeverity, ConditionCode, CurrentWeather, DayForecast, HistoricalDay,
+    HourlyForecast, ProviderCapabilities, DistanceUnit, SpeedUnit, WeatherAlert,
+};
+use crate::providers::dates;
 use reqwest;
 use tracing::debug;
 
-static WEATHERAPI: Lazy<WeatherApiProvider> = Lazy::new(|| {
-    let settings = Settings::new()
-        .expect("Failed to load settings");
-    
-    WeatherApiProvider {
-        api_key: settings.providers.weatherapi_api_key.clone(),
-        client: reqwest::Client::new(),
-    }
-});
+const WEATHERAPI_BASE_URL: &str = "http://api.weatherapi.com";
 
 pub struct WeatherApiProvider {
     api_key: String,
+    base_url: String,
     client: reqwest::Client,
 }
 
-#[derive(Deserialize)]
-struct WeatherApiResponse {
-    current: CurrentData,
-    location: LocationData,
-}
-
-#[derive(Deserialize)]
-struct LocationData {
-    country: String,
-}
-
 #[derive(Deserialize)]
 struct CurrentData {
     temp_c: f32,
     humidity: f32,
+    is_day: u8,
     condition: ConditionData,
     wind_kph: f32,
     wind_dir: String,
     uv: f32,
     vis_km: f32,
+    pressure_mb: f64,
+    precip_mm: f32,
 }
 
 #[derive(Deserialize)]
 struct ConditionData {
     text: String,
+    code: i32,
 }
 
 #[derive(Deserialize)]
 struct WeatherApiForecastResponse {
+    location: LocationData,
+    current: CurrentData,
     forecast: ForecastData,
 }
 
+#[derive(Deserialize)]
+struct LocationData {
+    #[serde(default)]
+    name: String,
+    /// Empty for places without a region
+    #[serde(default)]
+    region: String,
+    country: String,
+    #[serde(default)]
+    tz_id: String,
+    localtime_epoch: i64,
+}
+
+impl LocationData {
+    fn locality(&self) -> Option<Locality> {
+        if self.name.is_empty() {
+            return None;
+        }
+        Some(Locality {
+            name: self.name.clone(),
+            region: Some(self.region.clone()).filter(|region| !region.is_empty()),
+            country: self.country.clone(),
+            timezone: Some(self.tz_id.clone()).filter(|tz| !tz.is_empty()),
+        })
+    }
+}
+
 #[derive(Deserialize)]
 struct ForecastData {
     forecastday: Vec<ForecastDay>,
@@ -62,18 +80,207 @@ struct ForecastData {
 struct ForecastDay {
     date: String,
     day: DayData,
+    #[serde(default)]
+    hour: Vec<HourData>,
+}
+
+#[derive(Deserialize)]
+struct HourData {
+    time_epoch: i64,
+    temp_c: f32,
+    is_day: u8,
+    chance_of_rain: f32,
+    wind_kph: f32,
+    wind_degree: f32,
+    condition: ConditionData,
+}
+
+#[derive(Deserialize)]
+struct WeatherApiHistoryResponse {
+    forecast: HistoryData,
+}
+
+#[derive(Deserialize)]
+struct HistoryData {
+    forecastday: Vec<HistoryDay>,
+}
+
+#[derive(Deserialize)]
+struct HistoryDay {
+    date: String,
+    day: HistoryDayData,
+}
+
+#[derive(Deserialize)]
+struct HistoryDayData {
+    maxtemp_c: f64,
+    mintemp_c: f64,
+    avgtemp_c: f64,
+    maxwind_kph: f64,
+    totalprecip_mm: f64,
+    avghumidity: f64,
+    condition: ConditionData,
+}
+
+#[derive(Deserialize)]
+struct WeatherApiAirQualityResponse {
+    current: AirQualityCurrent,
+}
+
+#[derive(Deserialize)]
+struct AirQualityCurrent {
+    air_quality: AirQualityData,
+}
+
+/// Concentrations in µg/m³; WeatherAPI's own indices are left aside in
+/// favour of ones computed the same way for every provider
+#[derive(Deserialize)]
+struct AirQualityData {
+    pm2_5: Option<f64>,
+    pm10: Option<f64>,
+    o3: Option<f64>,
+    no2: Option<f64>,
+    so2: Option<f64>,
+    co: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct WeatherApiAlertsResponse {
+    alerts: AlertsData,
+}
+
+#[derive(Deserialize)]
+struct AlertsData {
+    #[serde(default)]
+    alert: Vec<AlertData>,
+}
+
+#[derive(Deserialize)]
+struct AlertData {
+    headline: String,
+    #[serde(default)]
+    severity: String,
+    event: String,
+    /// RFC 3339 with offset; empty when open-ended
+    #[serde(default)]
+    effective: String,
+    #[serde(default)]
+    expires: String,
+    #[serde(default)]
+    desc: String,
+}
+
+impl AlertData {
+    /// US feeds end the headline with the issuing office, e.g.
+    /// "... until March 21 at 8:00PM EDT by NWS Boston MA".
+    fn agency(&self) -> Option<String> {
+        self.headline
+            .rsplit_once(" by ")
+            .map(|(_, agency)| agency.trim().to_string())
+            .filter(|agency| !agency.is_empty())
+    }
 }
 
 #[derive(Deserialize)]
 struct DayData {
     maxtemp_c: f32,
     mintemp_c: f32,
+    totalprecip_mm: f32,
+    daily_chance_of_rain: f32,
     condition: ConditionData,
 }
 
 impl WeatherApiProvider {
-    pub fn new() -> &'static Self {
-        &WEATHERAPI
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self::with_base_url(api_key, WEATHERAPI_BASE_URL)
+    }
+
+    pub fn with_base_url(api_key: impl Into<String>, base_url: &str) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn fetch_forecast(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        days: i32,
+    ) -> Result<WeatherApiForecastResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!(
+            "{}/v1/forecast.json?key={}&q={},{}&days={}&aqi=no",
+            self.base_url, self.api_key, latitude, longitude, days
+        );
+        debug!(days, "Fetching forecast from WeatherAPI");
+
+        // Shared by current weather, daily and hourly forecasts
+        Ok(self.client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    async fn fetch_alerts(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<WeatherApiAlertsResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!(
+            "{}/v1/forecast.json?key={}&q={},{}&days=1&aqi=no&alerts=yes",
+            self.base_url, self.api_key, latitude, longitude
+        );
+        debug!("Fetching alerts from WeatherAPI");
+
+        Ok(self.client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    fn alert_severity(severity: &str) -> AlertSeverity {
+        match severity.to_ascii_lowercase().as_str() {
+            "minor" => AlertSeverity::Minor,
+            "moderate" => AlertSeverity::Moderate,
+            "severe" => AlertSeverity::Severe,
+            "extreme" => AlertSeverity::Extreme,
+            _ => AlertSeverity::Unknown,
+        }
+    }
+
+    /// Maps a WeatherAPI condition code onto the canonical taxonomy, see
+    /// https://www.weatherapi.com/docs/weather_conditions.json
+    fn condition_code(code: i32) -> ConditionCode {
+        match code {
+            1000 => ConditionCode::Clear,
+            1003 => ConditionCode::PartlyCloudy,
+            1006 => ConditionCode::Cloudy,
+            1009 => ConditionCode::Overcast,
+            1030 => ConditionCode::Mist,
+            1135 | 1147 => ConditionCode::Fog,
+            1087 | 1273 | 1276 | 1279 | 1282 => ConditionCode::Thunderstorm,
+            1150 | 1153 => ConditionCode::Drizzle,
+            1072 | 1168 | 1171 => ConditionCode::FreezingDrizzle,
+            1063 | 1180 | 1183 => ConditionCode::LightRain,
+            1186 | 1189 => ConditionCode::Rain,
+            1192 | 1195 => ConditionCode::HeavyRain,
+            1198 | 1201 => ConditionCode::FreezingRain,
+            1240 | 1243 | 1246 => ConditionCode::RainShowers,
+            1069 | 1204 | 1207 | 1249 | 1252 => ConditionCode::Sleet,
+            1066 | 1210 | 1213 => ConditionCode::LightSnow,
+            1114 | 1216 | 1219 => ConditionCode::Snow,
+            1117 | 1222 | 1225 => ConditionCode::HeavySnow,
+            1255 | 1258 => ConditionCode::SnowShowers,
+            1237 | 1261 | 1264 => ConditionCode::IcePellets,
+            _ => ConditionCode::Unknown,
+        }
     }
 
     fn wind_direction_to_degrees(&self, direction: &str) -> f64 {
@@ -105,42 +312,63 @@ impl WeatherProvider for WeatherApiProvider {
         "WeatherAPI".to_string()
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            max_forecast_days: 14,
+            max_forecast_hours: 14 * 24,
+            supported_fields: vec![
+                "temperature",
+                "condition",
+                "wind_speed",
+                "wind_direction",
+                "humidity",
+                "visibility",
+                "pressure",
+                "uv_index",
+                "max_temp",
+                "min_temp",
+                "country",
+                "rain_chance",
+                "precipitation_mm",
+            ],
+            supports_alerts: true,
+            supports_air_quality: true,
+            supports_historical: true,
+        }
+    }
+
     async fn get_current_weather(
         &self,
         latitude: f64,
         longitude: f64,
     ) -> Result<CurrentWeather, Box<dyn std::error::Error + Send + Sync>> {
-        let url = format!(
-            "http://api.weatherapi.com/v1/current.json?key={}&q={},{}&aqi=no",
-            self.api_key, latitude, longitude
-        );
-        debug!(url = %url, "Fetching weather from WeatherAPI");
-
-        let response = self.client
-            .get(&url)
-            .send()
-            .await?;
-            
-        debug!(status = ?response.status(), "WeatherAPI response status");
-
-        let weather_response: WeatherApiResponse = response.json().await?;
+        // The forecast endpoint also carries current conditions, plus the
+        // day's extremes and precipitation chance that current.json lacks
+        let weather_response = self.fetch_forecast(latitude, longitude, 1).await?;
         debug!(
             temperature = weather_response.current.temp_c,
             condition = %weather_response.current.condition.text,
             "Received weather data"
         );
+        let today = weather_response.forecast.forecastday.first().map(|d| &d.day);
 
         let weather = CurrentWeather {
             temperature: weather_response.current.temp_c as f64,
             humidity: weather_response.current.humidity as f64,
             condition: weather_response.current.condition.text,
-            wind_speed: weather_response.current.wind_kph as f64,
+            condition_code: Self::condition_code(weather_response.current.condition.code),
+            is_day: Some(weather_response.current.is_day == 1),
+            wind_speed: SpeedUnit::KilometersPerHour.to_canonical(weather_response.current.wind_kph as f64),
             wind_direction: self.wind_direction_to_degrees(&weather_response.current.wind_dir) as f64,
-            uv_index: weather_response.current.uv as f64,
-            visibility: weather_response.current.vis_km as f64,
-            country: weather_response.location.country,
-            max_temp: (weather_response.current.temp_c + 2.0) as f64,
-            min_temp: (weather_response.current.temp_c - 2.0) as f64,
+            uv_index: Some(weather_response.current.uv as f64),
+            visibility: Some(DistanceUnit::Kilometers.to_canonical(weather_response.current.vis_km as f64)),
+            pressure: Some(weather_response.current.pressure_mb),
+            country: weather_response.location.country.clone(),
+            max_temp: today.map(|d| d.maxtemp_c as f64),
+            min_temp: today.map(|d| d.mintemp_c as f64),
+            precipitation_probability: today.map(|d| d.daily_chance_of_rain as f64),
+            precipitation_mm: Some(weather_response.current.precip_mm as f64),
+            locality: weather_response.location.locality(),
         };
 
         Ok(weather)
@@ -152,27 +380,142 @@ impl WeatherProvider for WeatherApiProvider {
         longitude: f64,
         days: i32,
     ) -> Result<Vec<DayForecast>, Box<dyn std::error::Error + Send + Sync>> {
+        let response = self.fetch_forecast(latitude, longitude, days).await?;
+        let locality = response.location.locality();
+
+        Ok(response.forecast.forecastday
+            .into_iter()
+            .map(|f| DayForecast {
+                date: f.date,
+                max_temp: Some(f.day.maxtemp_c),
+                min_temp: Some(f.day.mintemp_c),
+                condition: f.day.condition.text,
+                condition_code: Self::condition_code(f.day.condition.code),
+                precipitation_probability: Some(f.day.daily_chance_of_rain),
+                precipitation_mm: Some(f.day.totalprecip_mm),
+                locality: locality.clone(),
+            })
+            .collect())
+    }
+
+    async fn get_hourly_forecast(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        hours: i32,
+    ) -> Result<Vec<HourlyForecast>, Box<dyn std::error::Error + Send + Sync>> {
+        // Hours are grouped per day, and the first day has already partly passed
+        let days = ((hours.max(0) + 23) / 24 + 1).min(14);
+        let response = self.fetch_forecast(latitude, longitude, days).await?;
+
+        let now = response.location.localtime_epoch;
+        let current_hour = now - now.rem_euclid(3600);
+
+        Ok(response.forecast.forecastday
+            .into_iter()
+            .flat_map(|day| day.hour)
+            .filter(|h| h.time_epoch >= current_hour)
+            .take(hours.max(0) as usize)
+            .map(|h| HourlyForecast {
+                timestamp: h.time_epoch,
+                temperature: h.temp_c as f64,
+                precipitation_probability: Some(h.chance_of_rain as f64),
+                wind_speed: SpeedUnit::KilometersPerHour.to_canonical(h.wind_kph as f64),
+                wind_direction: h.wind_degree as f64,
+                condition: h.condition.text,
+                condition_code: Self::condition_code(h.condition.code),
+                is_day: Some(h.is_day == 1),
+            })
+            .collect())
+    }
+
+    async fn get_alerts(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Vec<WeatherAlert>, Box<dyn std::error::Error + Send + Sync>> {
+        let response = self.fetch_alerts(latitude, longitude).await?;
+
+        Ok(response.alerts.alert
+            .into_iter()
+            .map(|a| WeatherAlert {
+                severity: Self::alert_severity(&a.severity),
+                agency: a.agency(),
+                starts_at: dates::parse_rfc3339(&a.effective),
+                ends_at: dates::parse_rfc3339(&a.expires),
+                event: a.event,
+                headline: a.headline,
+                description: a.desc,
+            })
+            .collect())
+    }
+
+    async fn get_air_quality(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<AirQuality, Box<dyn std::error::Error + Send + Sync>> {
         let url = format!(
-            "http://api.weatherapi.com/v1/forecast.json?key={}&q={},{}&days={}&aqi=no",
-            self.api_key, latitude, longitude, days
+            "{}/v1/current.json?key={}&q={},{}&aqi=yes",
+            self.base_url, self.api_key, latitude, longitude
         );
+        debug!("Fetching air quality from WeatherAPI");
 
-        let response: WeatherApiForecastResponse = self.client
+        let response: WeatherApiAirQualityResponse = self.client
             .get(&url)
             .send()
             .await?
+            .error_for_status()?
             .json()
             .await?;
+        let air = response.current.air_quality;
 
-        Ok(response.forecast.forecastday
+        Ok(AirQuality {
+            pm2_5: air.pm2_5,
+            pm10: air.pm10,
+            o3: air.o3,
+            no2: air.no2,
+            so2: air.so2,
+            co: air.co,
+        })
+    }
+
+    async fn get_historical(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        date: &str,
+    ) -> Result<HistoricalDay, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!(
+            "{}/v1/history.json?key={}&q={},{}&dt={}",
+            self.base_url, self.api_key, latitude, longitude, date
+        );
+        debug!(date, "Fetching history from WeatherAPI");
+
+        let response: WeatherApiHistoryResponse = self.client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let history = response.forecast.forecastday
             .into_iter()
-            .map(|f| DayForecast {
-                date: f.date,
-                max_temp: f.day.maxtemp_c,
-                min_temp: f.day.mintemp_c,
-                condition: f.day.condition.text,
-            })
-            .collect())
+            .next()
+            .ok_or_else(|| format!("WeatherAPI has no history for {}", date))?;
+        let day = history.day;
+
+        Ok(HistoricalDay {
+            date: history.date,
+            max_temp: day.maxtemp_c,
+            min_temp: day.mintemp_c,
+            avg_temp: Some(day.avgtemp_c),
+            condition: day.condition.text,
+            condition_code: Self::condition_code(day.condition.code),
+            precipitation_mm: Some(day.totalprecip_mm),
+            max_wind_speed: Some(SpeedUnit::KilometersPerHour.to_canonical(day.maxwind_kph)),
+            avg_humidity: Some(day.avghumidity),
+        })
     }
 }
 
@@ -180,12 +523,39 @@ impl WeatherProvider for WeatherApiProvider {
 mod tests {
     use super::*;
 
+    const FORECAST_FIXTURE: &str = include_str!("../tests/fixtures/weatherapi_forecast.json");
+    const ALERTS_FIXTURE: &str = include_str!("../tests/fixtures/weatherapi_alerts.json");
+    const AIR_QUALITY_FIXTURE: &str = include_str!("../tests/fixtures/weatherapi_air_quality.json");
+    const HISTORY_FIXTURE: &str = include_str!("../tests/fixtures/weatherapi_history.json");
+
+    async fn serve_fixture(path: &str, body: &str) -> mockito::ServerGuard {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", mockito::Matcher::Regex(format!("^{}", path)))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create_async()
+            .await;
+        server
+    }
+
+    /// Answers every request under `path` as WeatherAPI does a rejected key.
+    async fn serve_rejection(path: &str) -> mockito::ServerGuard {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", mockito::Matcher::Regex(format!("^{}", path)))
+            .with_status(401)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"error":{"code":2006,"message":"API key is invalid."}}"#)
+            .create_async()
+            .await;
+        server
+    }
+
     #[test]
     fn test_wind_direction_to_degrees() {
-        let provider = WeatherApiProvider {
-            api_key: "test_key".to_string(),
-            client: reqwest::Client::new(),
-        };
+        let provider = WeatherApiProvider::new("test_key");
 
         assert_eq!(provider.wind_direction_to_degrees("N"), 0.0);
         assert_eq!(provider.wind_direction_to_degrees("E"), 90.0);
@@ -193,4 +563,158 @@ mod tests {
         assert_eq!(provider.wind_direction_to_degrees("W"), 270.0);
         assert_eq!(provider.wind_direction_to_degrees("NW"), 315.0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_condition_code() {
+        assert_eq!(WeatherApiProvider::condition_code(1000), ConditionCode::Clear);
+        assert_eq!(WeatherApiProvider::condition_code(1003), ConditionCode::PartlyCloudy);
+        assert_eq!(WeatherApiProvider::condition_code(1195), ConditionCode::HeavyRain);
+        assert_eq!(WeatherApiProvider::condition_code(1276), ConditionCode::Thunderstorm);
+        assert_eq!(WeatherApiProvider::condition_code(1258), ConditionCode::SnowShowers);
+        assert_eq!(WeatherApiProvider::condition_code(42), ConditionCode::Unknown);
+    }
+
+    #[tokio::test]
+    async fn test_get_hourly_forecast_from_fixture() {
+        let server = serve_fixture("/v1/forecast.json", FORECAST_FIXTURE).await;
+        let provider = WeatherApiProvider::with_base_url("test_key", &server.url());
+
+        let hourly = provider.get_hourly_forecast(51.5, -0.12, 15).await.unwrap();
+
+        // Starts at the local 13:00 hour and crosses into the next day
+        assert_eq!(hourly.len(), 15);
+        assert_eq!(hourly[0].timestamp, 1710939600);
+        assert_eq!(hourly[0].condition, "Light rain");
+        assert_eq!(hourly[0].condition_code, ConditionCode::LightRain);
+        assert_eq!(hourly[0].is_day, Some(true));
+        assert_eq!(hourly[0].precipitation_probability, Some(70.0));
+        assert_eq!(hourly[14].timestamp, 1710939600 + 14 * 3600);
+    }
+
+    #[tokio::test]
+    async fn test_daily_values_from_fixture() {
+        let server = serve_fixture("/v1/forecast.json", FORECAST_FIXTURE).await;
+        let provider = WeatherApiProvider::with_base_url("test_key", &server.url());
+
+        let weather = provider.get_current_weather(51.5, -0.12).await.unwrap();
+        assert_eq!(weather.max_temp, Some(13.0));
+        assert!((weather.wind_speed - 13.0 / 3.6).abs() < 1e-6);
+        assert_eq!(weather.visibility, Some(10_000.0));
+        assert_eq!(weather.pressure, Some(1012.0));
+        assert_eq!(weather.min_temp, Some(7.0));
+        assert_eq!(weather.precipitation_probability, Some(88.0));
+        assert!((weather.precipitation_mm.unwrap() - 0.2).abs() < 1e-6);
+        let locality = weather.locality.unwrap();
+        assert_eq!(locality.name, "London");
+        assert_eq!(locality.region.as_deref(), Some("City of London, Greater London"));
+        assert_eq!(locality.timezone.as_deref(), Some("Europe/London"));
+
+        let forecast = provider.get_forecast(51.5, -0.12, 2).await.unwrap();
+        assert_eq!(forecast[0].condition_code, ConditionCode::Rain);
+        assert_eq!(forecast[1].locality.as_ref().unwrap().timezone.as_deref(), Some("Europe/London"));
+        assert_eq!(forecast[1].precipitation_probability, Some(72.0));
+        assert_eq!(forecast[1].precipitation_mm, Some(2.1));
+    }
+
+    #[tokio::test]
+    async fn test_get_alerts_from_fixture() {
+        let server = serve_fixture("/v1/forecast.json", ALERTS_FIXTURE).await;
+        let provider = WeatherApiProvider::with_base_url("test_key", &server.url());
+
+        let alerts = provider.get_alerts(42.36, -71.06).await.unwrap();
+
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[0].event, "Flood Warning");
+        assert_eq!(alerts[0].severity, AlertSeverity::Severe);
+        assert_eq!(alerts[0].agency.as_deref(), Some("NWS Boston MA"));
+        // 2024-03-20T09:47:00-04:00
+        assert_eq!(alerts[0].starts_at, Some(1710942420));
+        assert_eq!(alerts[0].ends_at, Some(1711065600));
+        assert!(alerts[0].description.contains("Charles River"));
+
+        assert_eq!(alerts[1].severity, AlertSeverity::Moderate);
+        assert_eq!(alerts[1].agency, None);
+        assert_eq!(alerts[1].ends_at, None);
+    }
+
+    #[tokio::test]
+    async fn test_get_alerts_without_alerts_in_effect() {
+        let body = r#"{"location": {"country": "UK"}, "alerts": {"alert": []}}"#;
+        let server = serve_fixture("/v1/forecast.json", body).await;
+        let provider = WeatherApiProvider::with_base_url("test_key", &server.url());
+
+        assert!(provider.get_alerts(51.5, -0.12).await.unwrap().is_empty());
+        assert!(provider.capabilities().supports_alerts);
+    }
+
+    #[tokio::test]
+    async fn test_forecast_with_rejected_key() {
+        let server = serve_rejection("/v1/forecast.json").await;
+        let provider = WeatherApiProvider::with_base_url("bad_key", &server.url());
+
+        for error in [
+            provider.get_current_weather(51.5, -0.12).await.unwrap_err(),
+            provider.get_forecast(51.5, -0.12, 2).await.unwrap_err(),
+            provider.get_hourly_forecast(51.5, -0.12, 6).await.unwrap_err(),
+        ] {
+            assert!(error.to_string().contains("401"), "unexpected error: {}", error);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_alerts_with_rejected_key() {
+        let server = serve_rejection("/v1/forecast.json").await;
+        let provider = WeatherApiProvider::with_base_url("bad_key", &server.url());
+
+        let error = provider.get_alerts(51.5, -0.12).await.unwrap_err().to_string();
+        assert!(error.contains("401"), "unexpected error: {}", error);
+    }
+
+    #[tokio::test]
+    async fn test_get_air_quality_from_fixture() {
+        let server = serve_fixture("/v1/current.json", AIR_QUALITY_FIXTURE).await;
+        let provider = WeatherApiProvider::with_base_url("test_key", &server.url());
+
+        let air = provider.get_air_quality(51.5, -0.12).await.unwrap();
+
+        assert_eq!(air.pm2_5, Some(7.4));
+        assert_eq!(air.o3, Some(130.0));
+        // Ozone at about 66 ppb rates moderate, even though WeatherAPI says 1
+        assert_eq!(air.us_epa_index(), Some(87));
+        assert_eq!(air.european_index(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_air_quality_with_rejected_key() {
+        let server = serve_rejection("/v1/current.json").await;
+        let provider = WeatherApiProvider::with_base_url("bad_key", &server.url());
+
+        let error = provider.get_air_quality(51.5, -0.12).await.unwrap_err().to_string();
+        assert!(error.contains("401"), "unexpected error: {}", error);
+    }
+
+    #[tokio::test]
+    async fn test_get_historical_from_fixture() {
+        let server = serve_fixture("/v1/history.json", HISTORY_FIXTURE).await;
+        let provider = WeatherApiProvider::with_base_url("test_key", &server.url());
+
+        let day = provider.get_historical(51.5, -0.12, "2024-03-10").await.unwrap();
+
+        assert_eq!(day.date, "2024-03-10");
+        assert_eq!(day.max_temp, 11.8);
+        assert_eq!(day.min_temp, 4.2);
+        assert_eq!(day.avg_temp, Some(7.9));
+        assert_eq!(day.condition_code, ConditionCode::LightRain);
+        assert_eq!(day.precipitation_mm, Some(3.6));
+        assert!((day.max_wind_speed.unwrap() - 5.0).abs() < 1e-9);
+        assert_eq!(day.avg_humidity, Some(81.0));
+    }
+
+    #[tokio::test]
+    async fn test_get_historical_without_data() {
+        let server = serve_fixture("/v1/history.json", r#"{"forecast": {"forecastday": []}}"#).await;
+        let provider = WeatherApiProvider::with_base_url("test_key", &server.url());
+
+        assert!(provider.get_historical(51.5, -0.12, "1999-01-01").await.is_err());
+    }
+}
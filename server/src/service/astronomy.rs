@@ -0,0 +1,41 @@
+use crate::astronomy::{Astronomy, MoonPhase};
+use crate::providers::dates;
+use crate::proto::weather as proto;
+
+/// Today's date at `longitude` in local mean solar time, the same day
+/// `astronomy::compute` works in.
+pub fn today(longitude: f64, now: i64) -> String {
+    dates::local_date(now, (longitude / 15.0 * 3600.0) as i64)
+}
+
+/// The wire form of one day's sun and moon events.
+pub fn describe(day: Astronomy) -> proto::Astronomy {
+    proto::Astronomy {
+        sunrise: day.sunrise,
+        sunset: day.sunset,
+        solar_noon: day.solar_noon,
+        civil_dawn: day.civil_dawn,
+        civil_dusk: day.civil_dusk,
+        nautical_dawn: day.nautical_dawn,
+        nautical_dusk: day.nautical_dusk,
+        day_length_secs: day.day_length_secs,
+        moonrise: day.moonrise,
+        moonset: day.moonset,
+        moon_phase: moon_phase(day.moon_phase) as i32,
+        moon_age: day.moon_age,
+        moon_illumination: day.moon_illumination,
+    }
+}
+
+fn moon_phase(phase: MoonPhase) -> proto::MoonPhase {
+    match phase {
+        MoonPhase::NewMoon => proto::MoonPhase::NewMoon,
+        MoonPhase::WaxingCrescent => proto::MoonPhase::WaxingCrescent,
+        MoonPhase::FirstQuarter => proto::MoonPhase::FirstQuarter,
+        MoonPhase::WaxingGibbous => proto::MoonPhase::WaxingGibbous,
+        MoonPhase::FullMoon => proto::MoonPhase::FullMoon,
+        MoonPhase::WaningGibbous => proto::MoonPhase::WaningGibbous,
+        MoonPhase::LastQuarter => proto::MoonPhase::LastQuarter,
+        MoonPhase::WaningCrescent => proto::MoonPhase::WaningCrescent,
+    }
+}
@@ -0,0 +1,121 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::providers::{Units, WeatherProvider};
+use crate::proto::weather::ProviderComparison;
+use crate::service::weather::weather_response;
+use tracing::debug;
+
+/// A registered provider to include in a comparison. Disabled providers are
+/// listed with an error instead of being called.
+pub struct Contender {
+    pub id: String,
+    pub enabled: bool,
+    pub provider: Arc<dyn WeatherProvider>,
+}
+
+/// Asks every contender for current weather at once and reports each
+/// answer, error and latency in the order given.
+pub async fn compare_providers(
+    contenders: Vec<Contender>,
+    deadline: Duration,
+    latitude: f64,
+    longitude: f64,
+    units: Units,
+) -> Vec<ProviderComparison> {
+    let handles: Vec<_> = contenders
+        .into_iter()
+        .map(|contender| {
+            tokio::spawn(async move {
+                let mut comparison = ProviderComparison {
+                    provider_id: contender.id,
+                    ..Default::default()
+                };
+                if !contender.enabled {
+                    comparison.error = "Provider is disabled".to_string();
+                    return comparison;
+                }
+
+                let started = Instant::now();
+                let result = tokio::time::timeout(
+                    deadline,
+                    contender.provider.get_current_weather(latitude, longitude),
+                )
+                .await;
+                comparison.latency_ms = started.elapsed().as_millis() as i64;
+
+                match result {
+                    Ok(Ok(weather)) => {
+                        comparison.weather = Some(weather_response(weather, contender.provider.name(), &units));
+                    }
+                    Ok(Err(e)) => comparison.error = e.to_string(),
+                    Err(_) => {
+                        comparison.error = format!("Timed out after {:?}", deadline);
+                        comparison.timed_out = true;
+                    }
+                }
+                debug!(
+                    provider = %comparison.provider_id,
+                    latency_ms = comparison.latency_ms,
+                    error = %comparison.error,
+                    "Provider comparison result"
+                );
+                comparison
+            })
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        match handle.await {
+            Ok(comparison) => results.push(comparison),
+            Err(e) => results.push(ProviderComparison {
+                error: format!("Provider task failed: {}", e),
+                ..Default::default()
+            }),
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::support::StubProvider;
+
+    fn contender(id: &str, enabled: bool, provider: StubProvider) -> Contender {
+        Contender {
+            id: id.to_string(),
+            enabled,
+            provider: Arc::new(provider),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compare_reports_every_provider() {
+        let contenders = vec![
+            contender("alpha", true, StubProvider::new("Alpha").with_temperature(12.0)),
+            contender("beta", true, StubProvider::new("Beta").failing()),
+            contender("gamma", true, StubProvider::new("Gamma").with_delay(Duration::from_secs(5))),
+            contender("delta", false, StubProvider::new("Delta")),
+        ];
+
+        let results = compare_providers(contenders, Duration::from_millis(50), 1.0, 2.0, Units::METRIC).await;
+
+        let ids: Vec<_> = results.iter().map(|r| r.provider_id.as_str()).collect();
+        assert_eq!(ids, ["alpha", "beta", "gamma", "delta"]);
+
+        let alpha = results[0].weather.as_ref().unwrap();
+        assert_eq!(alpha.temperature, 12.0);
+        assert_eq!(alpha.provider, "Alpha");
+        assert!(results[0].error.is_empty());
+
+        assert!(results[1].weather.is_none());
+        assert_eq!(results[1].error, "Beta is unavailable");
+
+        assert!(results[2].timed_out);
+        assert!(results[2].latency_ms >= 50);
+
+        assert_eq!(results[3].error, "Provider is disabled");
+        assert_eq!(results[3].latency_ms, 0);
+    }
+}
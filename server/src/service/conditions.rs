@@ -0,0 +1,34 @@
+use crate::providers::ConditionCode;
+use crate::proto::weather as proto;
+
+/// The wire value for a canonical condition code.
+pub fn describe(code: ConditionCode) -> proto::ConditionCode {
+    match code {
+        ConditionCode::Unknown => proto::ConditionCode::Unknown,
+        ConditionCode::Clear => proto::ConditionCode::Clear,
+        ConditionCode::PartlyCloudy => proto::ConditionCode::PartlyCloudy,
+        ConditionCode::Cloudy => proto::ConditionCode::Cloudy,
+        ConditionCode::Overcast => proto::ConditionCode::Overcast,
+        ConditionCode::Mist => proto::ConditionCode::Mist,
+        ConditionCode::Fog => proto::ConditionCode::Fog,
+        ConditionCode::Haze => proto::ConditionCode::Haze,
+        ConditionCode::Smoke => proto::ConditionCode::Smoke,
+        ConditionCode::Dust => proto::ConditionCode::Dust,
+        ConditionCode::Drizzle => proto::ConditionCode::Drizzle,
+        ConditionCode::FreezingDrizzle => proto::ConditionCode::FreezingDrizzle,
+        ConditionCode::LightRain => proto::ConditionCode::LightRain,
+        ConditionCode::Rain => proto::ConditionCode::Rain,
+        ConditionCode::HeavyRain => proto::ConditionCode::HeavyRain,
+        ConditionCode::FreezingRain => proto::ConditionCode::FreezingRain,
+        ConditionCode::RainShowers => proto::ConditionCode::RainShowers,
+        ConditionCode::Sleet => proto::ConditionCode::Sleet,
+        ConditionCode::LightSnow => proto::ConditionCode::LightSnow,
+        ConditionCode::Snow => proto::ConditionCode::Snow,
+        ConditionCode::HeavySnow => proto::ConditionCode::HeavySnow,
+        ConditionCode::SnowShowers => proto::ConditionCode::SnowShowers,
+        ConditionCode::IcePellets => proto::ConditionCode::IcePellets,
+        ConditionCode::Thunderstorm => proto::ConditionCode::Thunderstorm,
+        ConditionCode::Squall => proto::ConditionCode::Squall,
+        ConditionCode::Tornado => proto::ConditionCode::Tornado,
+    }
+}
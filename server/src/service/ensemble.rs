@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::providers::{ConditionCode, CurrentWeather, WeatherProvider};
+use crate::proto::weather::EnsembleSpread;
+use tracing::{debug, warn};
+
+/// Provider name that asks for a blend of every enabled provider.
+pub const ENSEMBLE_PROVIDER: &str = "ensemble";
+
+/// One provider's answer, with the fields it actually populates.
+#[derive(Debug, Clone)]
+pub struct Reading {
+    pub provider: String,
+    pub supported_fields: Vec<&'static str>,
+    pub weather: CurrentWeather,
+}
+
+#[derive(Debug)]
+pub struct Blend {
+    pub weather: CurrentWeather,
+    pub spread: EnsembleSpread,
+    pub providers: Vec<String>,
+}
+
+/// Queries all `providers` concurrently. Providers that fail or miss the
+/// deadline are left out.
+pub async fn gather(
+    providers: Vec<Arc<dyn WeatherProvider>>,
+    deadline: Duration,
+    latitude: f64,
+    longitude: f64,
+) -> Vec<Reading> {
+    let handles: Vec<_> = providers
+        .into_iter()
+        .map(|provider| {
+            tokio::spawn(async move {
+                let name = provider.name();
+                let result = tokio::time::timeout(
+                    deadline,
+                    provider.get_current_weather(latitude, longitude),
+                )
+                .await;
+                match result {
+                    Ok(Ok(weather)) => Some(Reading {
+                        provider: name,
+                        supported_fields: provider.capabilities().supported_fields,
+                        weather,
+                    }),
+                    Ok(Err(e)) => {
+                        warn!(provider = %name, error = %e, "Dropping provider from ensemble");
+                        None
+                    }
+                    Err(_) => {
+                        warn!(provider = %name, ?deadline, "Provider missed ensemble deadline");
+                        None
+                    }
+                }
+            })
+        })
+        .collect();
+
+    let mut readings = Vec::with_capacity(handles.len());
+    for handle in handles {
+        if let Ok(Some(reading)) = handle.await {
+            readings.push(reading);
+        }
+    }
+    debug!(count = readings.len(), "Gathered ensemble readings");
+    readings
+}
+
+/// Merges readings field by field. Only providers that populate a field
+/// contribute to it; `None` when there is nothing to blend.
+pub fn blend(readings: &[Reading]) -> Option<Blend> {
+    if readings.is_empty() {
+        return None;
+    }
+
+    // Readings that leave a field unknown (`None`) never contribute to it
+    let values = |field: &str, get: fn(&CurrentWeather) -> Option<f64>| -> Vec<f64> {
+        let supporting: Vec<f64> = readings
+            .iter()
+            .filter(|r| r.supported_fields.contains(&field))
+            .filter_map(|r| get(&r.weather))
+            .collect();
+        if supporting.is_empty() {
+            readings.iter().filter_map(|r| get(&r.weather)).collect()
+        } else {
+            supporting
+        }
+    };
+
+    let temperature = values("temperature", |w| Some(w.temperature));
+    let humidity = values("humidity", |w| Some(w.humidity));
+    let wind_speed = values("wind_speed", |w| Some(w.wind_speed));
+    let wind_direction = values("wind_direction", |w| Some(w.wind_direction));
+    let visibility = values("visibility", |w| w.visibility);
+    let uv_index = values("uv_index", |w| w.uv_index);
+    let pressure = values("pressure", |w| w.pressure);
+    let max_temp = values("max_temp", |w| w.max_temp);
+    let min_temp = values("min_temp", |w| w.min_temp);
+    let rain_chance = values("rain_chance", |w| w.precipitation_probability);
+    let precipitation_mm = values("precipitation_mm", |w| w.precipitation_mm);
+
+    let (condition, condition_code, agreement) = majority(readings.iter().map(|r| &r.weather));
+    let mean_direction = circular_mean(&wind_direction);
+
+    let weather = CurrentWeather {
+        temperature: median(&temperature),
+        humidity: median(&humidity),
+        condition,
+        condition_code,
+        is_day: readings.iter().find_map(|r| r.weather.is_day),
+        wind_speed: median(&wind_speed),
+        wind_direction: mean_direction,
+        uv_index: known_median(&uv_index),
+        visibility: known_median(&visibility),
+        pressure: known_median(&pressure),
+        country: readings
+            .iter()
+            .map(|r| r.weather.country.clone())
+            .find(|c| !c.is_empty())
+            .unwrap_or_default(),
+        max_temp: known_median(&max_temp),
+        min_temp: known_median(&min_temp),
+        precipitation_probability: known_median(&rain_chance),
+        precipitation_mm: known_median(&precipitation_mm),
+        locality: readings.iter().find_map(|r| r.weather.locality.clone()),
+    };
+
+    let spread = EnsembleSpread {
+        temperature: range(&temperature),
+        humidity: range(&humidity),
+        wind_speed: range(&wind_speed),
+        wind_direction: wind_direction
+            .iter()
+            .map(|d| angular_distance(*d, mean_direction))
+            .fold(0.0, f64::max),
+        visibility: range(&visibility),
+        uv_index: range(&uv_index),
+        max_temp: range(&max_temp),
+        min_temp: range(&min_temp),
+        condition_agreement: agreement,
+        rain_chance: range(&rain_chance),
+        precipitation_mm: range(&precipitation_mm),
+        pressure: range(&pressure),
+    };
+
+    Some(Blend {
+        weather,
+        spread,
+        providers: readings.iter().map(|r| r.provider.clone()).collect(),
+    })
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    match sorted.len() {
+        0 => 0.0,
+        n if n % 2 == 1 => sorted[n / 2],
+        n => (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0,
+    }
+}
+
+/// Median, or `None` when no reading reported the field.
+fn known_median(values: &[f64]) -> Option<f64> {
+    if values.is_empty() { None } else { Some(median(values)) }
+}
+
+fn range(values: &[f64]) -> f64 {
+    let max = values.iter().copied().fold(f64::MIN, f64::max);
+    let min = values.iter().copied().fold(f64::MAX, f64::min);
+    if values.is_empty() { 0.0 } else { max - min }
+}
+
+/// Mean of compass bearings in degrees, so 350° and 10° average to 0°.
+fn circular_mean(degrees: &[f64]) -> f64 {
+    let (sin, cos) = degrees.iter().fold((0.0, 0.0), |(s, c), d| {
+        let r = d.to_radians();
+        (s + r.sin(), c + r.cos())
+    });
+    if sin == 0.0 && cos == 0.0 {
+        return 0.0;
+    }
+    sin.atan2(cos).to_degrees().rem_euclid(360.0)
+}
+
+fn angular_distance(a: f64, b: f64) -> f64 {
+    let diff = (a - b).rem_euclid(360.0);
+    diff.min(360.0 - diff)
+}
+
+/// What a reading votes for: its canonical code, or its wording when the
+/// provider's code could not be mapped.
+#[derive(PartialEq, Eq, Hash)]
+enum Vote {
+    Code(ConditionCode),
+    Text(String),
+}
+
+impl Vote {
+    fn of(weather: &CurrentWeather) -> Self {
+        match weather.condition_code {
+            ConditionCode::Unknown => Vote::Text(weather.condition.to_lowercase()),
+            code => Vote::Code(code),
+        }
+    }
+}
+
+/// Most frequent condition (ties go to the earliest reading) and the share
+/// of readings that reported it. Providers word the same code differently,
+/// so votes are counted on the canonical code.
+fn majority<'a>(readings: impl Iterator<Item = &'a CurrentWeather>) -> (String, ConditionCode, f64) {
+    let readings: Vec<&CurrentWeather> = readings.collect();
+    let mut counts: HashMap<Vote, usize> = HashMap::new();
+    for weather in &readings {
+        *counts.entry(Vote::of(weather)).or_default() += 1;
+    }
+
+    let mut best: Option<(&CurrentWeather, usize)> = None;
+    for weather in &readings {
+        let count = counts[&Vote::of(weather)];
+        let better = match best {
+            Some((_, best_count)) => count > best_count,
+            None => true,
+        };
+        if better {
+            best = Some((weather, count));
+        }
+    }
+
+    match best {
+        Some((weather, count)) => (
+            weather.condition.clone(),
+            weather.condition_code,
+            count as f64 / readings.len() as f64,
+        ),
+        None => (String::new(), ConditionCode::Unknown, 0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::support::{sample_weather, StubProvider};
+
+    fn reading(provider: &str, fields: &[&'static str], update: impl FnOnce(&mut CurrentWeather)) -> Reading {
+        let mut weather = sample_weather();
+        update(&mut weather);
+        Reading {
+            provider: provider.to_string(),
+            supported_fields: fields.to_vec(),
+            weather,
+        }
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&[4.0, 1.0, 2.0, 3.0]), 2.5);
+        assert_eq!(median(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_circular_mean_wraps_north() {
+        assert!(angular_distance(circular_mean(&[350.0, 10.0]), 0.0) < 1e-9);
+        assert!((circular_mean(&[80.0, 100.0]) - 90.0).abs() < 1e-9);
+        assert_eq!(angular_distance(350.0, 10.0), 20.0);
+    }
+
+    #[test]
+    fn test_majority_condition() {
+        let weather = |condition: &str, code| CurrentWeather {
+            condition: condition.to_string(),
+            condition_code: code,
+            ..sample_weather()
+        };
+        let readings = [
+            weather("Clouds", ConditionCode::Overcast),
+            weather("Light rain", ConditionCode::LightRain),
+            weather("Patchy light rain", ConditionCode::LightRain),
+        ];
+
+        let (condition, code, agreement) = majority(readings.iter());
+
+        assert_eq!(condition, "Light rain");
+        assert_eq!(code, ConditionCode::LightRain);
+        assert!((agreement - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_majority_falls_back_to_wording_for_unknown_codes() {
+        let weather = |condition: &str| CurrentWeather {
+            condition: condition.to_string(),
+            condition_code: ConditionCode::Unknown,
+            ..sample_weather()
+        };
+        let readings = [weather("Rain"), weather("Clouds"), weather("rain")];
+
+        let (condition, code, agreement) = majority(readings.iter());
+
+        assert_eq!(condition, "Rain");
+        assert_eq!(code, ConditionCode::Unknown);
+        assert!((agreement - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_blend_uses_only_supporting_providers() {
+        let all = ["temperature", "uv_index", "wind_direction"];
+        let readings = vec![
+            reading("a", &all, |w| { w.temperature = 12.0; w.uv_index = Some(4.0); w.wind_direction = 350.0; }),
+            reading("b", &all, |w| { w.temperature = 15.0; w.uv_index = Some(6.0); w.wind_direction = 10.0; }),
+            // Reports a UV index without declaring support for it
+            reading("c", &["temperature", "wind_direction"], |w| { w.temperature = 13.0; w.uv_index = Some(0.0); w.wind_direction = 0.0; }),
+        ];
+
+        let blend = blend(&readings).unwrap();
+
+        assert_eq!(blend.weather.temperature, 13.0);
+        assert_eq!(blend.spread.temperature, 3.0);
+        assert_eq!(blend.weather.uv_index, Some(5.0));
+        assert_eq!(blend.spread.uv_index, 2.0);
+        assert!(angular_distance(blend.weather.wind_direction, 0.0) < 1e-9);
+        assert!((blend.spread.wind_direction - 10.0).abs() < 1e-9);
+        assert_eq!(blend.providers, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_blend_skips_unknown_values() {
+        let fields = ["temperature", "max_temp", "min_temp"];
+        let readings = vec![
+            reading("a", &fields, |w| { w.max_temp = None; w.min_temp = None; }),
+            reading("b", &fields, |w| { w.max_temp = Some(18.0); w.min_temp = None; }),
+        ];
+
+        let blend = blend(&readings).unwrap();
+
+        assert_eq!(blend.weather.max_temp, Some(18.0));
+        assert_eq!(blend.weather.min_temp, None);
+        assert_eq!(blend.spread.max_temp, 0.0);
+    }
+
+    #[test]
+    fn test_blend_empty() {
+        assert!(blend(&[]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_gather_drops_failed_and_slow_providers() {
+        let providers: Vec<Arc<dyn WeatherProvider>> = vec![
+            Arc::new(StubProvider::new("fast")),
+            Arc::new(StubProvider::new("broken").failing()),
+            Arc::new(StubProvider::new("slow").with_delay(Duration::from_secs(5))),
+        ];
+
+        let readings = gather(providers, Duration::from_millis(50), 1.0, 2.0).await;
+
+        assert_eq!(readings.len(), 1);
+        assert_eq!(readings[0].provider, "fast");
+    }
+}
@@ -0,0 +1,117 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::error::{AppError, AppResult};
+use crate::providers::WeatherProvider;
+use tracing::{debug, warn};
+
+/// A value together with the provider that produced it.
+#[derive(Debug)]
+pub struct Served<T> {
+    pub value: T,
+    pub provider: String,
+    /// True when the first candidate failed and a later one served the data.
+    pub fallback_used: bool,
+}
+
+/// Calls `candidates` in order until one succeeds within `timeout`.
+pub async fn run_with_fallback<T, F, Fut>(
+    candidates: Vec<Arc<dyn WeatherProvider>>,
+    timeout: Duration,
+    call: F,
+) -> AppResult<Served<T>>
+where
+    F: Fn(Arc<dyn WeatherProvider>) -> Fut,
+    Fut: Future<Output = Result<T, Box<dyn std::error::Error + Send + Sync>>>,
+{
+    let mut failures = Vec::new();
+
+    for (index, provider) in candidates.into_iter().enumerate() {
+        let name = provider.name();
+        match tokio::time::timeout(timeout, call(provider)).await {
+            Ok(Ok(value)) => {
+                debug!(provider = %name, fallback_used = index > 0, "Provider served request");
+                return Ok(Served {
+                    value,
+                    provider: name,
+                    fallback_used: index > 0,
+                });
+            }
+            Ok(Err(e)) => {
+                warn!(provider = %name, error = %e, "Provider failed, trying next");
+                failures.push(format!("{}: {}", name, e));
+            }
+            Err(_) => {
+                warn!(provider = %name, ?timeout, "Provider timed out, trying next");
+                failures.push(format!("{}: timed out after {:?}", name, timeout));
+            }
+        }
+    }
+
+    Err(AppError::WeatherApi(format!("All providers failed: {}", failures.join("; "))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::support::StubProvider;
+
+    fn providers(stubs: Vec<StubProvider>) -> Vec<Arc<dyn WeatherProvider>> {
+        stubs.into_iter().map(|s| Arc::new(s) as Arc<dyn WeatherProvider>).collect()
+    }
+
+    #[tokio::test]
+    async fn test_first_provider_serves() {
+        let candidates = providers(vec![StubProvider::new("alpha"), StubProvider::new("beta")]);
+
+        let served = run_with_fallback(candidates, Duration::from_secs(1), |p| async move {
+            p.get_current_weather(1.0, 2.0).await
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(served.provider, "alpha");
+        assert!(!served.fallback_used);
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_on_error_and_timeout() {
+        let candidates = providers(vec![
+            StubProvider::new("alpha").failing(),
+            StubProvider::new("beta").with_delay(Duration::from_secs(5)),
+            StubProvider::new("gamma").with_temperature(3.0),
+        ]);
+
+        let served = run_with_fallback(candidates, Duration::from_millis(50), |p| async move {
+            p.get_current_weather(1.0, 2.0).await
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(served.provider, "gamma");
+        assert_eq!(served.value.temperature, 3.0);
+        assert!(served.fallback_used);
+    }
+
+    #[tokio::test]
+    async fn test_all_providers_fail() {
+        let candidates = providers(vec![
+            StubProvider::new("alpha").failing(),
+            StubProvider::new("beta").failing(),
+        ]);
+
+        let error = run_with_fallback(candidates, Duration::from_secs(1), |p| async move {
+            p.get_current_weather(1.0, 2.0).await
+        })
+        .await
+        .unwrap_err();
+
+        match error {
+            AppError::WeatherApi(message) => {
+                assert!(message.contains("alpha"));
+                assert!(message.contains("beta"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+}
@@ -1 +1,8 @@
-pub mod weather;
\ No newline at end of file
+pub mod weather;
+pub mod fallback;
+pub mod ensemble;
+pub mod compare;
+pub mod units;
+pub mod conditions;
+pub mod astronomy;
+pub mod watch;
\ No newline at end of file
@@ -0,0 +1,159 @@
+use crate::error::{AppError, AppResult};
+use crate::providers::{DistanceUnit, PressureUnit, SpeedUnit, TemperatureUnit, Units};
+use crate::proto::weather as proto;
+
+/// Units a request asks for; no `units` message means metric.
+pub fn resolve(requested: Option<&proto::Units>) -> AppResult<Units> {
+    let Some(requested) = requested else {
+        return Ok(Units::METRIC);
+    };
+
+    let system = proto::UnitSystem::try_from(requested.system)
+        .map_err(|_| invalid("unit system", requested.system))?;
+    match system {
+        proto::UnitSystem::Metric => Ok(Units::METRIC),
+        proto::UnitSystem::Imperial => Ok(Units::IMPERIAL),
+        proto::UnitSystem::Custom => custom(requested),
+    }
+}
+
+fn custom(requested: &proto::Units) -> AppResult<Units> {
+    let metric = Units::METRIC;
+
+    let temperature = match proto::TemperatureUnit::try_from(requested.temperature)
+        .map_err(|_| invalid("temperature unit", requested.temperature))?
+    {
+        proto::TemperatureUnit::Unspecified => metric.temperature,
+        proto::TemperatureUnit::Celsius => TemperatureUnit::Celsius,
+        proto::TemperatureUnit::Fahrenheit => TemperatureUnit::Fahrenheit,
+        proto::TemperatureUnit::Kelvin => TemperatureUnit::Kelvin,
+    };
+    let speed = match proto::SpeedUnit::try_from(requested.speed)
+        .map_err(|_| invalid("speed unit", requested.speed))?
+    {
+        proto::SpeedUnit::Unspecified => metric.speed,
+        proto::SpeedUnit::MetersPerSecond => SpeedUnit::MetersPerSecond,
+        proto::SpeedUnit::KilometersPerHour => SpeedUnit::KilometersPerHour,
+        proto::SpeedUnit::MilesPerHour => SpeedUnit::MilesPerHour,
+        proto::SpeedUnit::Knots => SpeedUnit::Knots,
+    };
+    let distance = match proto::DistanceUnit::try_from(requested.distance)
+        .map_err(|_| invalid("distance unit", requested.distance))?
+    {
+        proto::DistanceUnit::Unspecified => metric.distance,
+        proto::DistanceUnit::Meters => DistanceUnit::Meters,
+        proto::DistanceUnit::Kilometers => DistanceUnit::Kilometers,
+        proto::DistanceUnit::Miles => DistanceUnit::Miles,
+    };
+    let pressure = match proto::PressureUnit::try_from(requested.pressure)
+        .map_err(|_| invalid("pressure unit", requested.pressure))?
+    {
+        proto::PressureUnit::Unspecified => metric.pressure,
+        proto::PressureUnit::Hectopascals => PressureUnit::Hectopascals,
+        proto::PressureUnit::InchesOfMercury => PressureUnit::InchesOfMercury,
+        proto::PressureUnit::MillimetersOfMercury => PressureUnit::MillimetersOfMercury,
+    };
+
+    Ok(Units { temperature, speed, distance, pressure })
+}
+
+fn invalid(what: &str, value: i32) -> AppError {
+    AppError::Invalidreqwest(format!("Unknown {}: {}", what, value))
+}
+
+/// The fully spelled-out units echoed back in responses.
+pub fn describe(units: Units) -> proto::Units {
+    let system = if units == Units::METRIC {
+        proto::UnitSystem::Metric
+    } else if units == Units::IMPERIAL {
+        proto::UnitSystem::Imperial
+    } else {
+        proto::UnitSystem::Custom
+    };
+
+    let temperature = match units.temperature {
+        TemperatureUnit::Celsius => proto::TemperatureUnit::Celsius,
+        TemperatureUnit::Fahrenheit => proto::TemperatureUnit::Fahrenheit,
+        TemperatureUnit::Kelvin => proto::TemperatureUnit::Kelvin,
+    };
+    let speed = match units.speed {
+        SpeedUnit::MetersPerSecond => proto::SpeedUnit::MetersPerSecond,
+        SpeedUnit::KilometersPerHour => proto::SpeedUnit::KilometersPerHour,
+        SpeedUnit::MilesPerHour => proto::SpeedUnit::MilesPerHour,
+        SpeedUnit::Knots => proto::SpeedUnit::Knots,
+    };
+    let distance = match units.distance {
+        DistanceUnit::Meters => proto::DistanceUnit::Meters,
+        DistanceUnit::Kilometers => proto::DistanceUnit::Kilometers,
+        DistanceUnit::Miles => proto::DistanceUnit::Miles,
+    };
+    let pressure = match units.pressure {
+        PressureUnit::Hectopascals => proto::PressureUnit::Hectopascals,
+        PressureUnit::InchesOfMercury => proto::PressureUnit::InchesOfMercury,
+        PressureUnit::MillimetersOfMercury => proto::PressureUnit::MillimetersOfMercury,
+    };
+
+    proto::Units {
+        system: system as i32,
+        temperature: temperature as i32,
+        speed: speed as i32,
+        distance: distance as i32,
+        pressure: pressure as i32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_named_systems() {
+        assert_eq!(resolve(None).unwrap(), Units::METRIC);
+
+        let imperial = proto::Units {
+            system: proto::UnitSystem::Imperial as i32,
+            // Ignored outside custom mode
+            speed: proto::SpeedUnit::Knots as i32,
+            ..Default::default()
+        };
+        assert_eq!(resolve(Some(&imperial)).unwrap(), Units::IMPERIAL);
+    }
+
+    #[test]
+    fn test_resolve_custom_defaults_to_metric() {
+        let custom = proto::Units {
+            system: proto::UnitSystem::Custom as i32,
+            speed: proto::SpeedUnit::Knots as i32,
+            ..Default::default()
+        };
+
+        let units = resolve(Some(&custom)).unwrap();
+
+        assert_eq!(units.speed, SpeedUnit::Knots);
+        assert_eq!(units.temperature, TemperatureUnit::Celsius);
+        assert_eq!(units.distance, DistanceUnit::Kilometers);
+        assert_eq!(describe(units).system, proto::UnitSystem::Custom as i32);
+    }
+
+    #[test]
+    fn test_resolve_rejects_unknown_values() {
+        let custom = proto::Units {
+            system: proto::UnitSystem::Custom as i32,
+            pressure: 42,
+            ..Default::default()
+        };
+
+        assert!(matches!(resolve(Some(&custom)), Err(AppError::Invalidreqwest(_))));
+    }
+
+    #[test]
+    fn test_describe_fills_every_dimension() {
+        let described = describe(Units::IMPERIAL);
+
+        assert_eq!(described.system, proto::UnitSystem::Imperial as i32);
+        assert_eq!(described.temperature, proto::TemperatureUnit::Fahrenheit as i32);
+        assert_eq!(described.speed, proto::SpeedUnit::MilesPerHour as i32);
+        assert_eq!(described.distance, proto::DistanceUnit::Miles as i32);
+        assert_eq!(described.pressure, proto::PressureUnit::InchesOfMercury as i32);
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use crate::config::WatchConfig;
+use crate::providers::{CurrentWeather, ProviderRegistry};
+use crate::service::fallback::run_with_fallback;
+use tracing::{debug, warn};
+
+/// Smallest differences worth pushing to watchers; anything below is
+/// provider noise between two refreshes.
+const TEMPERATURE_STEP: f64 = 0.5;
+const WIND_SPEED_STEP: f64 = 1.0;
+const HUMIDITY_STEP: f64 = 5.0;
+const PRECIPITATION_STEP: f64 = 0.2;
+
+/// The latest current weather at a watched location.
+#[derive(Debug, Clone)]
+pub struct Reading {
+    pub weather: CurrentWeather,
+    pub provider: String,
+    pub fallback_used: bool,
+}
+
+/// One poller per rounded location and requested provider.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PollKey {
+    latitude: i64,
+    longitude: i64,
+    provider: String,
+}
+
+type Pollers = Arc<Mutex<HashMap<PollKey, Arc<watch::Sender<Option<Reading>>>>>>;
+
+/// Shares one background poller per location between every WatchWeather
+/// subscriber, so N watchers cost one upstream call per interval. A poller
+/// stops on its first tick without subscribers.
+pub struct WatchHub {
+    registry: Arc<ProviderRegistry>,
+    interval: Duration,
+    scale: f64,
+    pollers: Pollers,
+}
+
+impl WatchHub {
+    pub fn new(registry: Arc<ProviderRegistry>, config: &WatchConfig) -> Self {
+        Self {
+            registry,
+            interval: Duration::from_secs(config.poll_interval_secs.max(1)),
+            scale: 10f64.powi(config.precision as i32),
+            pollers: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Readings at this location, starting with `None` until the first
+    /// refresh lands. Starts a poller unless one already runs.
+    pub fn subscribe(&self, latitude: f64, longitude: f64, provider: &str) -> watch::Receiver<Option<Reading>> {
+        let key = PollKey {
+            latitude: (latitude * self.scale).round() as i64,
+            longitude: (longitude * self.scale).round() as i64,
+            provider: provider.to_ascii_lowercase(),
+        };
+        let mut pollers = self.pollers.lock().unwrap();
+        if let Some(sender) = pollers.get(&key) {
+            return sender.subscribe();
+        }
+
+        debug!(?key, "Starting location poller");
+        let (sender, receiver) = watch::channel(None);
+        let sender = Arc::new(sender);
+        pollers.insert(key.clone(), sender.clone());
+        tokio::spawn(poll(
+            self.registry.clone(),
+            self.pollers.clone(),
+            key,
+            sender,
+            self.interval,
+            self.scale,
+        ));
+        receiver
+    }
+
+    /// Locations currently being polled.
+    pub fn active_pollers(&self) -> usize {
+        self.pollers.lock().unwrap().len()
+    }
+}
+
+async fn poll(
+    registry: Arc<ProviderRegistry>,
+    pollers: Pollers,
+    key: PollKey,
+    sender: Arc<watch::Sender<Option<Reading>>>,
+    interval: Duration,
+    scale: f64,
+) {
+    let (latitude, longitude) = (key.latitude as f64 / scale, key.longitude as f64 / scale);
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+        {
+            // Checked under the lock so a new subscriber cannot slip in as the poller leaves
+            let mut pollers = pollers.lock().unwrap();
+            if sender.receiver_count() == 0 {
+                debug!(?key, "Stopping location poller without subscribers");
+                pollers.remove(&key);
+                return;
+            }
+        }
+
+        let result = match registry.candidates(&key.provider) {
+            Ok(candidates) => {
+                run_with_fallback(candidates, registry.call_timeout(), |provider| async move {
+                    provider.get_current_weather(latitude, longitude).await
+                })
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(served) => {
+                let reading = Reading {
+                    weather: served.value,
+                    provider: served.provider,
+                    fallback_used: served.fallback_used,
+                };
+                sender.send_if_modified(|latest| update(latest, reading));
+            }
+            // Watchers keep the last reading until a refresh succeeds again
+            Err(e) => warn!(?key, error = %e, "Failed to refresh watched location"),
+        }
+    }
+}
+
+/// Replaces `latest` with `reading` if it differs meaningfully, returning
+/// whether watchers should be told.
+fn update(latest: &mut Option<Reading>, reading: Reading) -> bool {
+    let changed = match latest {
+        Some(previous) => changed_meaningfully(&previous.weather, &reading.weather),
+        None => true,
+    };
+    if changed {
+        *latest = Some(reading);
+    }
+    changed
+}
+
+fn changed_meaningfully(previous: &CurrentWeather, current: &CurrentWeather) -> bool {
+    let moved = |a: f64, b: f64, step: f64| (a - b).abs() >= step;
+    let moved_optional = |a: Option<f64>, b: Option<f64>, step: f64| match (a, b) {
+        (Some(a), Some(b)) => moved(a, b, step),
+        (a, b) => a.is_some() != b.is_some(),
+    };
+
+    previous.condition_code != current.condition_code
+        || previous.is_day != current.is_day
+        || moved(previous.temperature, current.temperature, TEMPERATURE_STEP)
+        || moved(previous.wind_speed, current.wind_speed, WIND_SPEED_STEP)
+        || moved(previous.humidity, current.humidity, HUMIDITY_STEP)
+        || moved_optional(previous.precipitation_mm, current.precipitation_mm, PRECIPITATION_STEP)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{ConditionCode, ProviderEntry};
+    use crate::tests::support::{sample_weather, StubProvider};
+
+    fn config() -> WatchConfig {
+        WatchConfig {
+            poll_interval_secs: 60,
+            precision: 2,
+            max_locations: 5,
+        }
+    }
+
+    fn hub(stub: Arc<StubProvider>) -> WatchHub {
+        let mut registry = ProviderRegistry::new();
+        registry.register(ProviderEntry {
+            id: "stub".to_string(),
+            display_name: "Stub".to_string(),
+            aliases: Vec::new(),
+            enabled: true,
+            provider: stub,
+        }).unwrap();
+        registry.set_fallback_chain(vec!["stub".to_string()]).unwrap();
+        WatchHub::new(Arc::new(registry), &config())
+    }
+
+    #[test]
+    fn test_only_meaningful_changes_count() {
+        let previous = sample_weather();
+        let mut current = sample_weather();
+
+        current.temperature += 0.3;
+        current.humidity += 2.0;
+        assert!(!changed_meaningfully(&previous, &current));
+
+        current.temperature += 0.3;
+        assert!(changed_meaningfully(&previous, &current));
+
+        let mut current = sample_weather();
+        current.condition_code = ConditionCode::Rain;
+        assert!(changed_meaningfully(&previous, &current));
+
+        let mut current = sample_weather();
+        current.precipitation_mm = None;
+        assert!(changed_meaningfully(&previous, &current));
+    }
+
+    #[test]
+    fn test_update_keeps_reading_on_noise() {
+        let reading = |temperature: f64| Reading {
+            weather: CurrentWeather { temperature, ..sample_weather() },
+            provider: "Stub".to_string(),
+            fallback_used: false,
+        };
+        let mut latest = None;
+
+        assert!(update(&mut latest, reading(20.0)));
+        assert!(!update(&mut latest, reading(20.2)));
+        assert_eq!(latest.as_ref().unwrap().weather.temperature, 20.0);
+        assert!(update(&mut latest, reading(21.0)));
+        assert_eq!(latest.unwrap().weather.temperature, 21.0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_subscribers_share_one_poller() {
+        let stub = Arc::new(StubProvider::new("Stub"));
+        let hub = hub(stub.clone());
+
+        let mut first = hub.subscribe(51.5074, -0.1278, "");
+        // Rounds to the same location
+        let mut second = hub.subscribe(51.5071, -0.1281, "");
+        assert_eq!(hub.active_pollers(), 1);
+
+        first.changed().await.unwrap();
+        second.changed().await.unwrap();
+        assert_eq!(stub.calls(), 1);
+        assert_eq!(first.borrow().as_ref().unwrap().provider, "Stub");
+
+        // Two more refreshes, neither of which changes anything
+        tokio::time::sleep(Duration::from_secs(150)).await;
+        assert_eq!(stub.calls(), 3);
+        assert!(!first.has_changed().unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_poller_stops_without_subscribers() {
+        let stub = Arc::new(StubProvider::new("Stub"));
+        let hub = hub(stub.clone());
+
+        let mut receiver = hub.subscribe(1.0, 2.0, "");
+        receiver.changed().await.unwrap();
+        drop(receiver);
+
+        tokio::time::sleep(Duration::from_secs(150)).await;
+        assert_eq!(hub.active_pollers(), 0);
+        assert_eq!(stub.calls(), 1);
+    }
+}
@@ -1,38 +1,357 @@
 use tonic::{Request, Response, Status};
-use crate::providers::{OpenWeatherProvider, WeatherApiProvider, WeatherProvider};
+use std::sync::Arc;
+use crate::providers::{
+    dates, AlertSeverity as ProviderAlertSeverity, CurrentWeather, DayForecast as ProviderDayForecast,
+    HistoricalDay as ProviderHistoricalDay, HourlyForecast as ProviderHourlyForecast, ProviderRegistry, Units,
+    WeatherAlert as ProviderWeatherAlert,
+};
+use crate::astronomy;
+use crate::storage::{unix_now, Stored, WeatherStore};
+use crate::service::compare::{self, Contender};
+use crate::service::ensemble::{self, ENSEMBLE_PROVIDER};
+use crate::service::fallback::run_with_fallback;
+use crate::service::watch::{Reading, WatchHub};
+use crate::service::conditions;
+use crate::service::astronomy as sky;
+use crate::service::units;
+use crate::config::WatchConfig;
+use crate::error::AppError;
+use crate::geocoding::{GeocoderChain, Locality, Place};
 use crate::proto::weather::weather_service_server::WeatherService;
 use crate::proto::weather::{
     WeatherRequest, WeatherResponse,
     ForecastRequest, ForecastResponse,
-    DayForecast,
+    HourlyForecastRequest, HourlyForecastResponse, HourlyForecast,
+    DayForecast, ListProvidersRequest, ListProvidersResponse, ProviderInfo,
+    CompareProvidersResponse, EnsembleSpread,
+    AlertsRequest, AlertsResponse, AlertSeverity, WeatherAlert,
+    AirQualityRequest, AirQualityResponse,
+    AstronomyRequest, AstronomyResponse,
+    HistoricalRequest, HistoricalResponse, HistoricalDay,
+    WatchWeatherRequest, WeatherUpdate, Coordinates,
+    BatchWeatherRequest, BatchWeatherResponse, LocationWeather,
+    SearchLocationsRequest, SearchLocationsResponse,
+    ReverseGeocodeRequest, ReverseGeocodeResponse, Location,
 };
-use crate::error::{AppError, AppResult};
-use tracing::{info, error, debug};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{info, error, debug, warn};
+
+/// Longest date range a single GetHistorical call may cover.
+const MAX_HISTORICAL_DAYS: i64 = 31;
+/// Most locations a single GetWeatherForLocations call may ask for.
+const MAX_BATCH_LOCATIONS: usize = 50;
+/// Locations of one batch looked up at the same time.
+const BATCH_CONCURRENCY: usize = 8;
+/// Days of one historical range fetched at the same time.
+const HISTORICAL_CONCURRENCY: usize = 4;
+/// Places returned by SearchLocations when the request sets no limit.
+const DEFAULT_SEARCH_LIMIT: usize = 5;
+/// Most places a single SearchLocations call may return.
+const MAX_SEARCH_LIMIT: usize = 20;
 
 pub struct WeatherServiceImpl {
-    openweather: &'static OpenWeatherProvider,
-    weatherapi: &'static WeatherApiProvider,
+    registry: Arc<ProviderRegistry>,
+    store: Option<Arc<WeatherStore>>,
+    max_stale_secs: i64,
+    watch: WatchHub,
+    max_watch_locations: usize,
+    geocoders: GeocoderChain,
 }
 
 impl WeatherServiceImpl {
-    pub fn new() -> Self {
+    pub fn new(registry: ProviderRegistry) -> Self {
+        let registry = Arc::new(registry);
+        let watch = WatchConfig::default();
         Self {
-            openweather: &OpenWeatherProvider::new(),
-            weatherapi: &WeatherApiProvider::new(),
+            watch: WatchHub::new(registry.clone(), &watch),
+            max_watch_locations: watch.max_locations,
+            registry,
+            store: None,
+            max_stale_secs: 0,
+            geocoders: GeocoderChain::default(),
         }
     }
 
-    fn get_provider(&self, provider_name: &str) -> AppResult<&dyn WeatherProvider> {
-        match provider_name.to_lowercase().as_str() {
-            "openweather" => Ok(self.openweather),
-            "weatherapi" => Ok(self.weatherapi),
-            _ => Err(AppError::Invalidreqwest(format!("Invalid provider: {}", provider_name))),
+    /// Geocoders behind SearchLocations and ReverseGeocode, also naming the
+    /// place of weather responses whose provider does not. Without them both
+    /// RPCs are unimplemented.
+    pub fn with_geocoders(mut self, geocoders: GeocoderChain) -> Self {
+        self.geocoders = geocoders;
+        self
+    }
+
+    /// Polling interval and limits for WatchWeather subscriptions. Pollers
+    /// call the providers of `upstream`, which should be the registry before
+    /// any cache was wrapped around it: otherwise a change upstream only
+    /// reaches watchers once the cached copy expires.
+    pub fn with_watch(mut self, upstream: ProviderRegistry, config: &WatchConfig) -> Self {
+        self.watch = WatchHub::new(Arc::new(upstream), config);
+        self.max_watch_locations = config.max_locations;
+        self
+    }
+
+    /// Serves data from `store`, marked stale, when every provider fails.
+    /// Entries older than `max_stale_secs` are not served.
+    pub fn with_store(mut self, store: Arc<WeatherStore>, max_stale_secs: u64) -> Self {
+        self.store = Some(store);
+        self.max_stale_secs = max_stale_secs as i64;
+        self
+    }
+
+    async fn stale_current(&self, latitude: f64, longitude: f64) -> Option<Stored<CurrentWeather>> {
+        let store = self.store.as_ref()?;
+        match store.latest_current(latitude, longitude).await {
+            Ok(stored) => stored.filter(|s| s.age_secs() <= self.max_stale_secs),
+            Err(e) => {
+                warn!(error = %e, "Failed to read stale current weather");
+                None
+            }
+        }
+    }
+
+    async fn stale_forecast(&self, latitude: f64, longitude: f64, days: i32) -> Option<Stored<Vec<ProviderDayForecast>>> {
+        let store = self.store.as_ref()?;
+        match store.latest_forecast(latitude, longitude, days).await {
+            Ok(stored) => stored.filter(|s| s.age_secs() <= self.max_stale_secs),
+            Err(e) => {
+                warn!(error = %e, "Failed to read stale forecast");
+                None
+            }
         }
     }
+
+    /// The place at a coordinate according to the geocoders, or `None` when
+    /// none is configured or knows one; weather is served either way.
+    async fn locate(&self, latitude: f64, longitude: f64) -> Option<Location> {
+        match self.geocoders.reverse(latitude, longitude).await {
+            Ok(answer) => Some(describe_locality(answer.value)),
+            Err(AppError::Unsupported(_) | AppError::NotFound(_)) => None,
+            Err(e) => {
+                warn!(error = %e, "Failed to reverse geocode");
+                None
+            }
+        }
+    }
+
+    /// Current weather from `provider` and the fallback chain, or the
+    /// ensemble blend, or stale stored data when every provider fails.
+    async fn current_weather(&self, latitude: f64, longitude: f64, provider: &str, units: Units) -> Result<WeatherResponse, Status> {
+        let mut response = if provider.eq_ignore_ascii_case(ENSEMBLE_PROVIDER) {
+            self.get_ensemble_weather(latitude, longitude, units).await?
+        } else {
+            self.provider_weather(latitude, longitude, provider, units).await?
+        };
+        // Payloads that name no place leave it to the geocoders
+        if response.location.is_none() {
+            response.location = self.locate(latitude, longitude).await;
+        }
+        Ok(response)
+    }
+
+    async fn provider_weather(&self, latitude: f64, longitude: f64, provider: &str, units: Units) -> Result<WeatherResponse, Status> {
+        let candidates = self.registry.candidates(provider).map_err(Status::from)?;
+        let result = run_with_fallback(candidates, self.registry.call_timeout(), |provider| async move {
+            provider.get_current_weather(latitude, longitude).await
+        })
+        .await;
+        let served = match result {
+            Ok(served) => served,
+            Err(e) => {
+                error!(?e, "Error getting weather");
+                let stale = self.stale_current(latitude, longitude).await.ok_or_else(|| Status::from(e))?;
+                warn!(provider = %stale.provider, age_secs = stale.age_secs(), "Serving stale weather");
+                let mut response = weather_response(stale.value, stale.provider, &units);
+                response.stale = true;
+                response.fetched_at = stale.fetched_at;
+                return Ok(response);
+            }
+        };
+
+        debug!(weather = ?served.value, "Weather data received");
+        let mut response = weather_response(served.value, served.provider, &units);
+        response.fallback_used = served.fallback_used;
+        Ok(response)
+    }
+
+    async fn get_ensemble_weather(&self, latitude: f64, longitude: f64, units: Units) -> Result<WeatherResponse, Status> {
+        let providers = self.registry.enabled();
+        let readings = ensemble::gather(providers, self.registry.call_timeout(), latitude, longitude).await;
+
+        let blend = ensemble::blend(&readings).ok_or_else(|| {
+            error!("No provider answered in time for the ensemble");
+            Status::from(AppError::WeatherApi("No provider returned data for the ensemble".to_string()))
+        })?;
+
+        debug!(providers = ?blend.providers, spread = ?blend.spread, "Blended ensemble weather");
+        let mut response = weather_response(blend.weather, "Ensemble".to_string(), &units);
+        response.spread = Some(spread_response(blend.spread, &units));
+        response.contributing_providers = blend.providers;
+        Ok(response)
+    }
+}
+
+fn forecast_response(forecasts: Vec<ProviderDayForecast>, units: &Units) -> Vec<DayForecast> {
+    let temperature = |t: f32| units.temperature.from_canonical(t as f64) as f32;
+    forecasts
+        .into_iter()
+        .map(|f| DayForecast {
+            date: f.date,
+            max_temp: f.max_temp.map(temperature),
+            min_temp: f.min_temp.map(temperature),
+            condition: f.condition,
+            condition_code: conditions::describe(f.condition_code) as i32,
+            rain_chance: f.precipitation_probability,
+            precipitation_mm: f.precipitation_mm,
+            astronomy: None,
+        })
+        .collect()
+}
+
+/// Fills in each day's sun and moon times.
+fn attach_astronomy(forecasts: &mut [DayForecast], latitude: f64, longitude: f64) {
+    for day in forecasts {
+        day.astronomy = astronomy::compute(latitude, longitude, &day.date).map(sky::describe);
+    }
+}
+
+fn hourly_response(hours: Vec<ProviderHourlyForecast>, units: &Units) -> Vec<HourlyForecast> {
+    hours
+        .into_iter()
+        .map(|h| HourlyForecast {
+            timestamp: h.timestamp,
+            temperature: units.temperature.from_canonical(h.temperature),
+            precipitation_probability: h.precipitation_probability,
+            wind_speed: units.speed.from_canonical(h.wind_speed),
+            wind_direction: h.wind_direction,
+            condition: h.condition,
+            condition_code: conditions::describe(h.condition_code) as i32,
+            is_day: h.is_day,
+        })
+        .collect()
+}
+
+/// Every date from `start` to `end` inclusive, all of them before `today`.
+fn historical_dates(start: &str, end: &str, today: &str) -> Result<Vec<String>, AppError> {
+    let day_number = |date: &str| {
+        dates::parse_date(date)
+            .map(|(year, month, day)| dates::days_from_civil(year, month, day))
+            .ok_or_else(|| AppError::Invalidreqwest(format!("invalid date '{}', expected YYYY-MM-DD", date)))
+    };
+    let (first, last) = (day_number(start)?, day_number(end)?);
+    if last < first {
+        return Err(AppError::Invalidreqwest("end_date is before start_date".to_string()));
+    }
+    if last - first >= MAX_HISTORICAL_DAYS {
+        return Err(AppError::Invalidreqwest(format!("at most {} days can be requested", MAX_HISTORICAL_DAYS)));
+    }
+    if last >= day_number(today)? {
+        return Err(AppError::Invalidreqwest("historical dates must be in the past".to_string()));
+    }
+
+    Ok((first..=last)
+        .map(|days| {
+            let (year, month, day) = dates::civil_from_days(days);
+            format!("{:04}-{:02}-{:02}", year, month, day)
+        })
+        .collect())
+}
+
+fn historical_response(days: Vec<ProviderHistoricalDay>, units: &Units) -> Vec<HistoricalDay> {
+    days
+        .into_iter()
+        .map(|d| HistoricalDay {
+            date: d.date,
+            max_temp: units.temperature.from_canonical(d.max_temp),
+            min_temp: units.temperature.from_canonical(d.min_temp),
+            avg_temp: d.avg_temp.map(|t| units.temperature.from_canonical(t)),
+            condition: d.condition,
+            condition_code: conditions::describe(d.condition_code) as i32,
+            precipitation_mm: d.precipitation_mm,
+            max_wind_speed: d.max_wind_speed.map(|w| units.speed.from_canonical(w)),
+            avg_humidity: d.avg_humidity,
+        })
+        .collect()
+}
+
+fn watch_update(location: Coordinates, reading: Reading, units: &Units) -> WeatherUpdate {
+    let mut weather = weather_response(reading.weather, reading.provider, units);
+    weather.fallback_used = reading.fallback_used;
+    WeatherUpdate {
+        location: Some(location),
+        weather: Some(weather),
+    }
+}
+
+fn alerts_response(alerts: Vec<ProviderWeatherAlert>) -> Vec<WeatherAlert> {
+    alerts
+        .into_iter()
+        .map(|a| {
+            let severity = match a.severity {
+                ProviderAlertSeverity::Unknown => AlertSeverity::Unknown,
+                ProviderAlertSeverity::Minor => AlertSeverity::Minor,
+                ProviderAlertSeverity::Moderate => AlertSeverity::Moderate,
+                ProviderAlertSeverity::Severe => AlertSeverity::Severe,
+                ProviderAlertSeverity::Extreme => AlertSeverity::Extreme,
+            };
+            WeatherAlert {
+                event: a.event,
+                severity: severity as i32,
+                headline: a.headline,
+                description: a.description,
+                agency: a.agency,
+                starts_at: a.starts_at,
+                ends_at: a.ends_at,
+            }
+        })
+        .collect()
+}
+
+/// Converts canonical provider values into the requested `units`.
+pub(crate) fn weather_response(weather: CurrentWeather, provider: String, units: &Units) -> WeatherResponse {
+    let temperature = |t: f64| units.temperature.from_canonical(t);
+    WeatherResponse {
+        temperature: temperature(weather.temperature),
+        humidity: weather.humidity,
+        condition: weather.condition,
+        condition_code: conditions::describe(weather.condition_code) as i32,
+        is_day: weather.is_day,
+        wind_speed: units.speed.from_canonical(weather.wind_speed),
+        wind_direction: weather.wind_direction,
+        uv_index: weather.uv_index,
+        visibility: weather.visibility.map(|v| units.distance.from_canonical(v)),
+        pressure: weather.pressure.map(|p| units.pressure.from_canonical(p)),
+        country: weather.country,
+        max_temp: weather.max_temp.map(temperature),
+        min_temp: weather.min_temp.map(temperature),
+        provider,
+        rain_chance: weather.precipitation_probability,
+        precipitation_mm: weather.precipitation_mm,
+        location: weather.locality.map(describe_locality),
+        units: Some(units::describe(*units)),
+        ..Default::default()
+    }
+}
+
+/// Spreads are differences, so temperatures convert without offset.
+fn spread_response(spread: EnsembleSpread, units: &Units) -> EnsembleSpread {
+    let temperature = |t: f64| units.temperature.delta_from_canonical(t);
+    EnsembleSpread {
+        temperature: temperature(spread.temperature),
+        wind_speed: units.speed.from_canonical(spread.wind_speed),
+        visibility: units.distance.from_canonical(spread.visibility),
+        max_temp: temperature(spread.max_temp),
+        min_temp: temperature(spread.min_temp),
+        pressure: units.pressure.from_canonical(spread.pressure),
+        ..spread
+    }
 }
 
 #[tonic::async_trait]
 impl WeatherService for WeatherServiceImpl {
+    type WatchWeatherStream = ReceiverStream<Result<WeatherUpdate, Status>>;
+
     async fn get_current_weather(
         &self,
         request: Request<WeatherRequest>
@@ -44,35 +363,11 @@ impl WeatherService for WeatherServiceImpl {
             provider = req.provider,
             "Received weather request"
         );
-        
-        let provider = self.get_provider(&req.provider).map_err(Status::from)?;
-        debug!("Using provider: {}", provider.name());
-        
-        match provider.get_current_weather(req.latitude, req.longitude).await {
-            Ok(weather) => {
-                debug!(?weather, "Weather data received");
-                let response = WeatherResponse {
-                    temperature: weather.temperature,
-                    humidity: weather.humidity,
-                    condition: weather.condition,
-                    wind_speed: weather.wind_speed,
-                    wind_direction: weather.wind_direction,
-                    uv_index: weather.uv_index,
-                    visibility: weather.visibility,
-                    country: weather.country,
-                    max_temp: weather.max_temp,
-                    min_temp: weather.min_temp,
-                    provider: provider.name(),
-                    rain_chance: 0.0,
-                };
-                debug!(?response, "Sending response");
-                Ok(Response::new(response))
-            },
-            Err(e) => {
-                error!(?e, "Error getting weather");
-                Err(AppError::WeatherApi(e.to_string()).into())
-            }
-        }
+
+        let units = units::resolve(req.units.as_ref()).map_err(Status::from)?;
+        let response = self.current_weather(req.latitude, req.longitude, &req.provider, units).await?;
+        debug!(?response, "Sending response");
+        Ok(Response::new(response))
     }
 
     async fn get_forecast(
@@ -86,38 +381,484 @@ impl WeatherService for WeatherServiceImpl {
             days = req.days,
             "Received forecast request"
         );
-        
-        let provider = self.get_provider(&req.provider).map_err(Status::from)?;
-
-        match provider.get_forecast(req.latitude, req.longitude, req.days).await {
-            Ok(forecasts) => {
-                let forecast_responses = forecasts
-                    .into_iter()
-                    .map(|f| DayForecast {
-                        date: f.date,
-                        max_temp: f.max_temp,
-                        min_temp: f.min_temp,
-                        condition: f.condition,
-                    })
-                    .collect();
-
-                debug!("Sending forecast response");
-                Ok(Response::new(ForecastResponse { forecasts: forecast_responses }))
+
+        let units = units::resolve(req.units.as_ref()).map_err(Status::from)?;
+        let candidates = self.registry.candidates(&req.provider).map_err(Status::from)?;
+        let (latitude, longitude, days) = (req.latitude, req.longitude, req.days);
+
+        let result = run_with_fallback(candidates, self.registry.call_timeout(), |provider| async move {
+            provider.get_forecast(latitude, longitude, days).await
+        })
+        .await;
+        let mut response = match result {
+            Ok(served) => ForecastResponse {
+                location: payload_location(&served.value),
+                forecasts: forecast_response(served.value, &units),
+                provider: served.provider,
+                fallback_used: served.fallback_used,
+                units: Some(units::describe(units)),
+                ..Default::default()
             },
             Err(e) => {
                 error!(?e, "Error getting forecast");
-                Err(AppError::WeatherApi(e.to_string()).into())
+                let stale = self.stale_forecast(latitude, longitude, days).await.ok_or_else(|| Status::from(e))?;
+                warn!(provider = %stale.provider, age_secs = stale.age_secs(), "Serving stale forecast");
+                ForecastResponse {
+                    location: payload_location(&stale.value),
+                    forecasts: forecast_response(stale.value, &units),
+                    provider: stale.provider,
+                    stale: true,
+                    fetched_at: stale.fetched_at,
+                    units: Some(units::describe(units)),
+                    ..Default::default()
+                }
             }
+        };
+        if req.include_astronomy {
+            attach_astronomy(&mut response.forecasts, latitude, longitude);
+        }
+        // Payloads that name no place leave it to the geocoders
+        if response.location.is_none() {
+            response.location = self.locate(latitude, longitude).await;
         }
+
+        debug!("Sending forecast response");
+        Ok(Response::new(response))
+    }
+
+    async fn get_hourly_forecast(
+        &self,
+        request: Request<HourlyForecastRequest>
+    ) -> Result<Response<HourlyForecastResponse>, Status> {
+        let req = request.into_inner();
+        info!(
+            latitude = req.latitude,
+            longitude = req.longitude,
+            hours = req.hours,
+            "Received hourly forecast request"
+        );
+
+        if req.hours <= 0 {
+            return Err(AppError::Invalidreqwest("hours must be positive".to_string()).into());
+        }
+        let units = units::resolve(req.units.as_ref()).map_err(Status::from)?;
+        let candidates = self.registry.candidates(&req.provider).map_err(Status::from)?;
+        let (latitude, longitude, hours) = (req.latitude, req.longitude, req.hours);
+
+        let served = run_with_fallback(candidates, self.registry.call_timeout(), |provider| async move {
+            provider.get_hourly_forecast(latitude, longitude, hours).await
+        })
+        .await
+        .map_err(|e| {
+            error!(?e, "Error getting hourly forecast");
+            Status::from(e)
+        })?;
+
+        debug!("Sending hourly forecast response");
+        Ok(Response::new(HourlyForecastResponse {
+            hours: hourly_response(served.value, &units),
+            provider: served.provider,
+            fallback_used: served.fallback_used,
+            units: Some(units::describe(units)),
+        }))
+    }
+
+    async fn list_providers(
+        &self,
+        _request: Request<ListProvidersRequest>
+    ) -> Result<Response<ListProvidersResponse>, Status> {
+        let providers = self.registry
+            .entries()
+            .map(|entry| {
+                let capabilities = entry.provider.capabilities();
+                ProviderInfo {
+                    id: entry.id.clone(),
+                    display_name: entry.display_name.clone(),
+                    enabled: entry.enabled,
+                    max_forecast_days: capabilities.max_forecast_days,
+                    supported_fields: capabilities.supported_fields
+                        .into_iter()
+                        .map(String::from)
+                        .collect(),
+                    aliases: entry.aliases.clone(),
+                    max_forecast_hours: capabilities.max_forecast_hours,
+                    supports_alerts: capabilities.supports_alerts,
+                    supports_air_quality: capabilities.supports_air_quality,
+                    supports_historical: capabilities.supports_historical,
+                }
+            })
+            .collect();
+
+        Ok(Response::new(ListProvidersResponse { providers }))
+    }
+
+    async fn compare_providers(
+        &self,
+        request: Request<WeatherRequest>
+    ) -> Result<Response<CompareProvidersResponse>, Status> {
+        let req = request.into_inner();
+        info!(
+            latitude = req.latitude,
+            longitude = req.longitude,
+            "Received provider comparison request"
+        );
+
+        let units = units::resolve(req.units.as_ref()).map_err(Status::from)?;
+        let contenders = self.registry
+            .entries()
+            .map(|entry| Contender {
+                id: entry.id.clone(),
+                enabled: entry.enabled,
+                provider: entry.provider.clone(),
+            })
+            .collect();
+        let results = compare::compare_providers(
+            contenders,
+            self.registry.call_timeout(),
+            req.latitude,
+            req.longitude,
+            units,
+        )
+        .await;
+
+        Ok(Response::new(CompareProvidersResponse { results }))
+    }
+
+    async fn get_alerts(
+        &self,
+        request: Request<AlertsRequest>
+    ) -> Result<Response<AlertsResponse>, Status> {
+        let req = request.into_inner();
+        info!(
+            latitude = req.latitude,
+            longitude = req.longitude,
+            provider = req.provider,
+            "Received alerts request"
+        );
+
+        let candidates = self.registry
+            .candidates_with(&req.provider, "weather alerts", |c| c.supports_alerts)
+            .map_err(Status::from)?;
+        let (latitude, longitude) = (req.latitude, req.longitude);
+
+        let served = run_with_fallback(candidates, self.registry.call_timeout(), |provider| async move {
+            provider.get_alerts(latitude, longitude).await
+        })
+        .await
+        .map_err(|e| {
+            error!(?e, "Error getting alerts");
+            Status::from(e)
+        })?;
+
+        debug!(count = served.value.len(), "Sending alerts response");
+        Ok(Response::new(AlertsResponse {
+            alerts: alerts_response(served.value),
+            provider: served.provider,
+            fallback_used: served.fallback_used,
+        }))
+    }
+
+    async fn get_air_quality(
+        &self,
+        request: Request<AirQualityRequest>
+    ) -> Result<Response<AirQualityResponse>, Status> {
+        let req = request.into_inner();
+        info!(
+            latitude = req.latitude,
+            longitude = req.longitude,
+            provider = req.provider,
+            "Received air quality request"
+        );
+
+        let candidates = self.registry
+            .candidates_with(&req.provider, "air quality", |c| c.supports_air_quality)
+            .map_err(Status::from)?;
+        let (latitude, longitude) = (req.latitude, req.longitude);
+
+        let served = run_with_fallback(candidates, self.registry.call_timeout(), |provider| async move {
+            provider.get_air_quality(latitude, longitude).await
+        })
+        .await
+        .map_err(|e| {
+            error!(?e, "Error getting air quality");
+            Status::from(e)
+        })?;
+
+        let air = served.value;
+        debug!(?air, "Sending air quality response");
+        Ok(Response::new(AirQualityResponse {
+            us_epa_index: air.us_epa_index().map(|i| i as i32),
+            european_index: air.european_index().map(|i| i as i32),
+            pm2_5: air.pm2_5,
+            pm10: air.pm10,
+            o3: air.o3,
+            no2: air.no2,
+            so2: air.so2,
+            co: air.co,
+            provider: served.provider,
+            fallback_used: served.fallback_used,
+        }))
+    }
+
+    async fn get_astronomy(
+        &self,
+        request: Request<AstronomyRequest>
+    ) -> Result<Response<AstronomyResponse>, Status> {
+        let req = request.into_inner();
+        info!(
+            latitude = req.latitude,
+            longitude = req.longitude,
+            date = req.date,
+            "Received astronomy request"
+        );
+
+        if !(-90.0..=90.0).contains(&req.latitude) || !(-180.0..=180.0).contains(&req.longitude) {
+            return Err(AppError::Invalidreqwest("coordinates out of range".to_string()).into());
+        }
+        let date = if req.date.is_empty() {
+            sky::today(req.longitude, unix_now())
+        } else {
+            req.date
+        };
+        let day = astronomy::compute(req.latitude, req.longitude, &date)
+            .ok_or_else(|| AppError::Invalidreqwest(format!("invalid date '{}', expected YYYY-MM-DD", date)))?;
+
+        debug!(?day, "Sending astronomy response");
+        Ok(Response::new(AstronomyResponse {
+            date,
+            astronomy: Some(sky::describe(day)),
+        }))
+    }
+
+    async fn get_historical(
+        &self,
+        request: Request<HistoricalRequest>
+    ) -> Result<Response<HistoricalResponse>, Status> {
+        let req = request.into_inner();
+        info!(
+            latitude = req.latitude,
+            longitude = req.longitude,
+            start_date = req.start_date,
+            end_date = req.end_date,
+            "Received historical request"
+        );
+
+        let end_date = if req.end_date.is_empty() { &req.start_date } else { &req.end_date };
+        // Today at the location in mean solar time, close enough to refuse days still under way
+        let today = sky::today(req.longitude, unix_now());
+        let days = historical_dates(&req.start_date, end_date, &today).map_err(Status::from)?;
+        let units = units::resolve(req.units.as_ref()).map_err(Status::from)?;
+        let candidates = self.registry
+            .candidates_with(&req.provider, "historical weather", |c| c.supports_historical)
+            .map_err(Status::from)?;
+        let (latitude, longitude) = (req.latitude, req.longitude);
+
+        // One provider serves the whole range, a few of its days at a time
+        let served = run_with_fallback(candidates, self.registry.call_timeout(), |provider| {
+            stream::iter(days.clone())
+                .map(move |date| {
+                    let provider = provider.clone();
+                    async move { provider.get_historical(latitude, longitude, &date).await }
+                })
+                .buffered(HISTORICAL_CONCURRENCY)
+                .try_collect::<Vec<ProviderHistoricalDay>>()
+        })
+        .await
+        .map_err(|e| {
+            error!(?e, "Error getting historical weather");
+            Status::from(e)
+        })?;
+
+        debug!(days = served.value.len(), "Sending historical response");
+        Ok(Response::new(HistoricalResponse {
+            days: historical_response(served.value, &units),
+            provider: served.provider,
+            fallback_used: served.fallback_used,
+            units: Some(units::describe(units)),
+        }))
+    }
+
+    async fn watch_weather(
+        &self,
+        request: Request<WatchWeatherRequest>
+    ) -> Result<Response<Self::WatchWeatherStream>, Status> {
+        let req = request.into_inner();
+        info!(
+            locations = req.locations.len(),
+            provider = req.provider,
+            "Received watch request"
+        );
+
+        if req.locations.is_empty() || req.locations.len() > self.max_watch_locations {
+            return Err(AppError::Invalidreqwest(format!(
+                "between 1 and {} locations can be watched",
+                self.max_watch_locations
+            )).into());
+        }
+        if req.locations.iter().any(|l| !(-90.0..=90.0).contains(&l.latitude) || !(-180.0..=180.0).contains(&l.longitude)) {
+            return Err(AppError::Invalidreqwest("coordinates out of range".to_string()).into());
+        }
+        let units = units::resolve(req.units.as_ref()).map_err(Status::from)?;
+        // Fails fast on an unknown provider instead of leaving the stream silent
+        self.registry.candidates(&req.provider).map_err(Status::from)?;
+
+        let (updates, stream) = mpsc::channel(req.locations.len() * 2);
+        for location in req.locations {
+            let mut readings = self.watch.subscribe(location.latitude, location.longitude, &req.provider);
+            let updates = updates.clone();
+            tokio::spawn(async move {
+                loop {
+                    let reading = readings.borrow_and_update().clone();
+                    if let Some(reading) = reading {
+                        let update = watch_update(location.clone(), reading, &units);
+                        if updates.send(Ok(update)).await.is_err() {
+                            break;
+                        }
+                    }
+                    tokio::select! {
+                        changed = readings.changed() => if changed.is_err() { break },
+                        // The client went away; dropping `readings` lets the poller stop
+                        _ = updates.closed() => break,
+                    }
+                }
+            });
+        }
+
+        Ok(Response::new(ReceiverStream::new(stream)))
+    }
+
+    async fn get_weather_for_locations(
+        &self,
+        request: Request<BatchWeatherRequest>
+    ) -> Result<Response<BatchWeatherResponse>, Status> {
+        let req = request.into_inner();
+        info!(
+            locations = req.locations.len(),
+            provider = req.provider,
+            "Received batch weather request"
+        );
+
+        if req.locations.len() > MAX_BATCH_LOCATIONS {
+            return Err(AppError::Invalidreqwest(format!(
+                "at most {} locations can be requested at once",
+                MAX_BATCH_LOCATIONS
+            )).into());
+        }
+        let units = units::resolve(req.units.as_ref()).map_err(Status::from)?;
+
+        // Each location succeeds or fails on its own, as GetCurrentWeather would
+        let provider = req.provider.as_str();
+        let results = stream::iter(req.locations)
+            .map(|location| async move {
+                let result = self.current_weather(location.latitude, location.longitude, provider, units).await;
+                match result {
+                    Ok(weather) => LocationWeather {
+                        location: Some(location),
+                        weather: Some(weather),
+                        ..Default::default()
+                    },
+                    Err(status) => LocationWeather {
+                        location: Some(location),
+                        error: status.message().to_string(),
+                        error_code: status.code() as i32,
+                        ..Default::default()
+                    },
+                }
+            })
+            .buffered(BATCH_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+
+        debug!(locations = results.len(), "Sending batch weather response");
+        Ok(Response::new(BatchWeatherResponse { results }))
+    }
+
+    async fn search_locations(
+        &self,
+        request: Request<SearchLocationsRequest>
+    ) -> Result<Response<SearchLocationsResponse>, Status> {
+        let req = request.into_inner();
+        info!(query = req.query, limit = req.limit, "Received location search request");
+
+        let query = req.query.trim();
+        if query.is_empty() {
+            return Err(AppError::Invalidreqwest("query must not be empty".to_string()).into());
+        }
+        let limit = match req.limit as usize {
+            0 => DEFAULT_SEARCH_LIMIT,
+            limit => limit.min(MAX_SEARCH_LIMIT),
+        };
+
+        let answer = self.geocoders.search(query, limit).await.map_err(|e| {
+            error!("Failed to search locations: {}", e);
+            Status::from(e)
+        })?;
+
+        let places = answer.value.into_iter().map(describe_place).collect::<Vec<_>>();
+        debug!(places = places.len(), source = answer.geocoder, "Sending location search response");
+        Ok(Response::new(SearchLocationsResponse {
+            places,
+            source: answer.geocoder,
+        }))
+    }
+
+    async fn reverse_geocode(
+        &self,
+        request: Request<ReverseGeocodeRequest>
+    ) -> Result<Response<ReverseGeocodeResponse>, Status> {
+        let req = request.into_inner();
+        info!(latitude = req.latitude, longitude = req.longitude, "Received reverse geocode request");
+
+        if !(-90.0..=90.0).contains(&req.latitude) || !(-180.0..=180.0).contains(&req.longitude) {
+            return Err(AppError::Invalidreqwest("coordinates out of range".to_string()).into());
+        }
+
+        let answer = self.geocoders.reverse(req.latitude, req.longitude).await.map_err(|e| {
+            error!("Failed to reverse geocode: {}", e);
+            Status::from(e)
+        })?;
+
+        debug!(location = answer.value.name, source = answer.geocoder, "Sending reverse geocode response");
+        Ok(Response::new(ReverseGeocodeResponse {
+            location: Some(describe_locality(answer.value)),
+            source: answer.geocoder,
+        }))
+    }
+}
+
+fn describe_locality(locality: Locality) -> Location {
+    Location {
+        name: locality.name,
+        region: locality.region.unwrap_or_default(),
+        country: locality.country,
+        timezone: locality.timezone.unwrap_or_default(),
+    }
+}
+
+/// The place a forecast's payload names, if any.
+fn payload_location(forecasts: &[ProviderDayForecast]) -> Option<Location> {
+    forecasts.first()?.locality.clone().map(describe_locality)
+}
+
+fn describe_place(place: Place) -> crate::proto::weather::Place {
+    crate::proto::weather::Place {
+        name: place.name,
+        state: place.state.unwrap_or_default(),
+        country: place.country,
+        latitude: place.latitude,
+        longitude: place.longitude,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
     use mockall::predicate::*;
     use mockall::mock;
-    use crate::providers::CurrentWeather;
+    use crate::config::CacheConfig;
+    use crate::providers::{CachedProvider, ConditionCode, ProviderEntry, WeatherProvider};
+    use crate::tests::support::{sample_alert, sample_locality, StubGeocoder, StubProvider};
 
     // Create mock for WeatherProvider
     mock! {
@@ -143,13 +884,19 @@ mod tests {
             temperature: 20.0,
             humidity: 50.0,
             condition: "Sunny".to_string(),
+            condition_code: ConditionCode::Clear,
+            is_day: Some(true),
             wind_speed: 10.0,
             wind_direction: 180.0,
-            uv_index: 5.0,
-            visibility: 10.0,
+            uv_index: Some(5.0),
+            visibility: Some(10_000.0),
+            pressure: None,
             country: "US".to_string(),
-            max_temp: 25.0,
-            min_temp: 15.0,
+            max_temp: Some(25.0),
+            min_temp: Some(15.0),
+            precipitation_probability: Some(40.0),
+            precipitation_mm: Some(0.5),
+            locality: None,
         };
 
         let mut mock_provider = MockWeatherProvider::new();
@@ -166,6 +913,7 @@ mod tests {
             longitude: -74.0,
             provider: "mockprovider".to_string(),
             client_id: "test_client".to_string(),
+            units: None,
         });
 
         let response = mock_provider
@@ -182,9 +930,12 @@ mod tests {
         let mock_forecasts = vec![
             DayForecast {
                 date: "2024-03-20".to_string(),
-                max_temp: 25.0,
-                min_temp: 15.0,
+                max_temp: Some(25.0),
+                min_temp: Some(15.0),
                 condition: "Sunny".to_string(),
+                rain_chance: Some(10.0),
+                precipitation_mm: Some(0.0),
+                ..Default::default()
             }
         ];
 
@@ -203,6 +954,8 @@ mod tests {
             days: 1,
             provider: "mockprovider".to_string(),
             client_id: "test_client".to_string(),
+            units: None,
+            include_astronomy: false,
         });
 
         let response = mock_provider
@@ -211,6 +964,936 @@ mod tests {
             .unwrap();
 
         assert_eq!(response[0].date, "2024-03-20");
-        assert_eq!(response[0].max_temp, 25.0);
+        assert_eq!(response[0].max_temp, Some(25.0));
     }
-} 
\ No newline at end of file
+
+    fn stub_service(enabled: bool) -> WeatherServiceImpl {
+        let mut registry = ProviderRegistry::new();
+        registry.register(ProviderEntry {
+            id: "stub".to_string(),
+            display_name: "Stub".to_string(),
+            aliases: vec!["stub-alias".to_string()],
+            enabled,
+            provider: Arc::new(StubProvider::new("Stub").with_temperature(12.5)),
+        }).unwrap();
+        WeatherServiceImpl::new(registry)
+    }
+
+    #[tokio::test]
+    async fn test_service_resolves_provider_from_registry() {
+        let service = stub_service(true);
+
+        let response = service
+            .get_current_weather(Request::new(WeatherRequest {
+                latitude: 40.0,
+                longitude: -74.0,
+                provider: "Stub-Alias".to_string(),
+                client_id: "test_client".to_string(),
+                units: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.temperature, 12.5);
+        assert_eq!(response.rain_chance, Some(30.0));
+        assert_eq!(response.provider, "Stub");
+    }
+
+    #[tokio::test]
+    async fn test_service_rejects_disabled_provider() {
+        let service = stub_service(false);
+
+        let status = service
+            .get_current_weather(Request::new(WeatherRequest {
+                latitude: 40.0,
+                longitude: -74.0,
+                provider: "stub".to_string(),
+                client_id: "test_client".to_string(),
+                units: None,
+            }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+    }
+
+    #[tokio::test]
+    async fn test_list_providers() {
+        let service = stub_service(false);
+
+        let response = service
+            .list_providers(Request::new(ListProvidersRequest {
+                client_id: "test_client".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.providers.len(), 1);
+        let info = &response.providers[0];
+        assert_eq!(info.id, "stub");
+        assert_eq!(info.display_name, "Stub");
+        assert!(!info.enabled);
+        assert_eq!(info.max_forecast_days, 7);
+        assert_eq!(info.max_forecast_hours, 48);
+        assert_eq!(info.supported_fields, ["temperature", "condition"]);
+        assert!(!info.supports_alerts);
+        assert!(!info.supports_air_quality);
+        assert!(!info.supports_historical);
+        assert_eq!(info.aliases, ["stub-alias"]);
+    }
+
+    fn chained_service(providers: Vec<(&str, StubProvider)>) -> WeatherServiceImpl {
+        let mut registry = ProviderRegistry::new();
+        let mut chain = Vec::new();
+        for (id, provider) in providers {
+            registry.register(ProviderEntry {
+                id: id.to_string(),
+                display_name: id.to_string(),
+                aliases: Vec::new(),
+                enabled: true,
+                provider: Arc::new(provider),
+            }).unwrap();
+            chain.push(id.to_string());
+        }
+        registry.set_fallback_chain(chain).unwrap();
+        registry.set_call_timeout(Duration::from_millis(50));
+        WeatherServiceImpl::new(registry)
+    }
+
+    #[tokio::test]
+    async fn test_current_weather_falls_back_when_requested_provider_fails() {
+        let service = chained_service(vec![
+            ("alpha", StubProvider::new("Alpha")),
+            ("beta", StubProvider::new("Beta").failing()),
+        ]);
+
+        let response = service
+            .get_current_weather(Request::new(WeatherRequest {
+                latitude: 40.0,
+                longitude: -74.0,
+                provider: "beta".to_string(),
+                client_id: "test_client".to_string(),
+                units: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.provider, "Alpha");
+        assert!(response.fallback_used);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_fields_are_absent_not_zero() {
+        let service = chained_service(vec![
+            ("alpha", StubProvider::new("Alpha").without_optional_fields()),
+            ("beta", StubProvider::new("Beta")),
+        ]);
+        let request = |provider: &str| Request::new(WeatherRequest {
+            latitude: 40.0,
+            longitude: -74.0,
+            provider: provider.to_string(),
+            client_id: "test_client".to_string(),
+            units: None,
+        });
+
+        let alpha = service.get_current_weather(request("alpha")).await.unwrap().into_inner();
+        assert_eq!(alpha.max_temp, None);
+        assert_eq!(alpha.min_temp, None);
+        assert_eq!(alpha.uv_index, None);
+        assert_eq!(alpha.visibility, None);
+        assert_eq!(alpha.rain_chance, None);
+        assert_eq!(alpha.precipitation_mm, None);
+
+        let beta = service.get_current_weather(request("beta")).await.unwrap().into_inner();
+        assert_eq!(beta.max_temp, Some(25.0));
+        assert_eq!(beta.min_temp, Some(15.0));
+        assert_eq!(beta.uv_index, Some(5.0));
+        assert_eq!(beta.precipitation_mm, Some(0.0));
+    }
+
+    #[tokio::test]
+    async fn test_condition_code_and_day_flag() {
+        let service = chained_service(vec![("alpha", StubProvider::new("Alpha"))]);
+
+        let current = service
+            .get_current_weather(Request::new(WeatherRequest {
+                latitude: 40.0,
+                longitude: -74.0,
+                provider: String::new(),
+                client_id: "test_client".to_string(),
+                units: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(current.condition, "Sunny");
+        assert_eq!(current.condition_code(), crate::proto::weather::ConditionCode::Clear);
+        assert_eq!(current.is_day, Some(true));
+
+        let hourly = service
+            .get_hourly_forecast(Request::new(HourlyForecastRequest {
+                latitude: 40.0,
+                longitude: -74.0,
+                hours: 2,
+                provider: String::new(),
+                client_id: "test_client".to_string(),
+                units: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(hourly.hours[0].condition_code(), crate::proto::weather::ConditionCode::Clear);
+        assert_eq!(hourly.hours[0].is_day, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_current_weather_in_requested_units() {
+        let service = chained_service(vec![("alpha", StubProvider::new("Alpha"))]);
+        let request = |units: Option<crate::proto::weather::Units>| Request::new(WeatherRequest {
+            latitude: 40.0,
+            longitude: -74.0,
+            provider: String::new(),
+            client_id: "test_client".to_string(),
+            units,
+        });
+
+        // Stub reports 20 °C, 10 m/s wind and 10 km visibility
+        let metric = service.get_current_weather(request(None)).await.unwrap().into_inner();
+        assert_eq!(metric.temperature, 20.0);
+        assert!((metric.wind_speed - 36.0).abs() < 1e-9);
+        assert_eq!(metric.visibility, Some(10.0));
+        assert_eq!(metric.units, Some(units::describe(Units::METRIC)));
+
+        let imperial = service
+            .get_current_weather(request(Some(units::describe(Units::IMPERIAL))))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!((imperial.temperature - 68.0).abs() < 1e-9);
+        assert!((imperial.max_temp.unwrap() - 77.0).abs() < 1e-9);
+        assert!((imperial.wind_speed - 22.369_363).abs() < 1e-6);
+        assert!((imperial.visibility.unwrap() - 6.213_712).abs() < 1e-6);
+        assert_eq!(imperial.units, Some(units::describe(Units::IMPERIAL)));
+    }
+
+    #[tokio::test]
+    async fn test_empty_provider_uses_chain_without_fallback_flag() {
+        let service = chained_service(vec![
+            ("alpha", StubProvider::new("Alpha")),
+            ("beta", StubProvider::new("Beta")),
+        ]);
+
+        let response = service
+            .get_forecast(Request::new(ForecastRequest {
+                latitude: 40.0,
+                longitude: -74.0,
+                days: 2,
+                provider: String::new(),
+                client_id: "test_client".to_string(),
+                units: None,
+                include_astronomy: false,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.forecasts.len(), 2);
+        assert_eq!(response.provider, "Alpha");
+        assert!(!response.fallback_used);
+    }
+
+    #[tokio::test]
+    async fn test_forecast_unavailable_when_chain_exhausted() {
+        let service = chained_service(vec![
+            ("alpha", StubProvider::new("Alpha").with_delay(Duration::from_secs(5))),
+            ("beta", StubProvider::new("Beta").failing()),
+        ]);
+
+        let status = service
+            .get_forecast(Request::new(ForecastRequest {
+                latitude: 40.0,
+                longitude: -74.0,
+                days: 2,
+                provider: String::new(),
+                client_id: "test_client".to_string(),
+                units: None,
+                include_astronomy: false,
+            }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), tonic::Code::Unavailable);
+    }
+
+    #[tokio::test]
+    async fn test_hourly_forecast_falls_back() {
+        let service = chained_service(vec![
+            ("alpha", StubProvider::new("Alpha").failing()),
+            ("beta", StubProvider::new("Beta")),
+        ]);
+
+        let response = service
+            .get_hourly_forecast(Request::new(HourlyForecastRequest {
+                latitude: 40.0,
+                longitude: -74.0,
+                hours: 6,
+                provider: "alpha".to_string(),
+                client_id: "test_client".to_string(),
+                units: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.hours.len(), 6);
+        assert_eq!(response.hours[1].timestamp - response.hours[0].timestamp, 3600);
+        assert_eq!(response.provider, "Beta");
+        assert!(response.fallback_used);
+    }
+
+    #[tokio::test]
+    async fn test_hourly_forecast_rejects_non_positive_hours() {
+        let service = chained_service(vec![("alpha", StubProvider::new("Alpha"))]);
+
+        let status = service
+            .get_hourly_forecast(Request::new(HourlyForecastRequest {
+                latitude: 40.0,
+                longitude: -74.0,
+                hours: 0,
+                provider: String::new(),
+                client_id: "test_client".to_string(),
+                units: None,
+            }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_ensemble_blends_enabled_providers() {
+        let service = chained_service(vec![
+            ("alpha", StubProvider::new("Alpha").with_temperature(10.0)),
+            ("beta", StubProvider::new("Beta").with_temperature(14.0)),
+            ("gamma", StubProvider::new("Gamma").with_temperature(11.0)),
+            ("delta", StubProvider::new("Delta").failing()),
+        ]);
+
+        let response = service
+            .get_current_weather(Request::new(WeatherRequest {
+                latitude: 40.0,
+                longitude: -74.0,
+                provider: "Ensemble".to_string(),
+                client_id: "test_client".to_string(),
+                units: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.provider, "Ensemble");
+        assert_eq!(response.temperature, 11.0);
+        assert_eq!(response.spread.unwrap().temperature, 4.0);
+        assert_eq!(response.contributing_providers, ["Alpha", "Beta", "Gamma"]);
+    }
+
+    #[tokio::test]
+    async fn test_ensemble_fails_when_no_provider_answers() {
+        let service = chained_service(vec![
+            ("alpha", StubProvider::new("Alpha").failing()),
+        ]);
+
+        let status = service
+            .get_current_weather(Request::new(WeatherRequest {
+                latitude: 40.0,
+                longitude: -74.0,
+                provider: "ensemble".to_string(),
+                client_id: "test_client".to_string(),
+                units: None,
+            }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), tonic::Code::Unavailable);
+    }
+
+    #[tokio::test]
+    async fn test_stale_data_served_when_all_providers_fail() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}", dir.path().join("weather.db").display());
+        let store = Arc::new(WeatherStore::connect(&url, 2).await.unwrap());
+        store.put_current("Alpha", 40.0, -74.0, &crate::tests::support::sample_weather()).await.unwrap();
+
+        let service = chained_service(vec![("alpha", StubProvider::new("Alpha").failing())])
+            .with_store(store, 3600);
+
+        let response = service
+            .get_current_weather(Request::new(WeatherRequest {
+                latitude: 40.0,
+                longitude: -74.0,
+                provider: String::new(),
+                client_id: "test_client".to_string(),
+                units: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert!(response.stale);
+        assert!(response.fetched_at > 0);
+        assert_eq!(response.provider, "Alpha");
+
+        // Nothing stored for the forecast, so the provider error surfaces
+        let status = service
+            .get_forecast(Request::new(ForecastRequest {
+                latitude: 40.0,
+                longitude: -74.0,
+                days: 2,
+                provider: String::new(),
+                client_id: "test_client".to_string(),
+                units: None,
+                include_astronomy: false,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unavailable);
+    }
+
+    fn alerts_request(provider: &str) -> Request<AlertsRequest> {
+        Request::new(AlertsRequest {
+            latitude: 42.36,
+            longitude: -71.06,
+            provider: provider.to_string(),
+            client_id: "test_client".to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_alerts_skip_providers_without_feed() {
+        let service = chained_service(vec![
+            ("alpha", StubProvider::new("Alpha")),
+            ("beta", StubProvider::new("Beta").with_alerts(vec![sample_alert()])),
+        ]);
+
+        let response = service.get_alerts(alerts_request("")).await.unwrap().into_inner();
+
+        assert_eq!(response.provider, "Beta");
+        assert!(!response.fallback_used);
+        assert_eq!(response.alerts.len(), 1);
+        let alert = &response.alerts[0];
+        assert_eq!(alert.event, "Flood Warning");
+        assert_eq!(alert.severity(), AlertSeverity::Severe);
+        assert_eq!(alert.agency.as_deref(), Some("NWS Boston MA"));
+        assert_eq!(alert.ends_at, Some(1711022400));
+    }
+
+    #[tokio::test]
+    async fn test_alerts_unavailable_is_not_an_empty_list() {
+        let service = chained_service(vec![
+            ("alpha", StubProvider::new("Alpha")),
+            ("beta", StubProvider::new("Beta").with_alerts(Vec::new())),
+        ]);
+
+        let status = service.get_alerts(alerts_request("alpha")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unimplemented);
+
+        let response = service.get_alerts(alerts_request("beta")).await.unwrap().into_inner();
+        assert!(response.alerts.is_empty());
+
+        let without_feed = chained_service(vec![("alpha", StubProvider::new("Alpha"))]);
+        let status = without_feed.get_alerts(alerts_request("")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unimplemented);
+    }
+
+    #[tokio::test]
+    async fn test_air_quality_reports_computed_indices() {
+        let air = crate::providers::AirQuality {
+            pm2_5: Some(40.0),
+            no2: Some(13.5),
+            ..Default::default()
+        };
+        let service = chained_service(vec![
+            ("alpha", StubProvider::new("Alpha")),
+            ("beta", StubProvider::new("Beta").with_air_quality(air)),
+        ]);
+        let request = |provider: &str| Request::new(AirQualityRequest {
+            latitude: 51.5,
+            longitude: -0.12,
+            provider: provider.to_string(),
+            client_id: "test_client".to_string(),
+        });
+
+        let response = service.get_air_quality(request("")).await.unwrap().into_inner();
+        assert_eq!(response.provider, "Beta");
+        assert_eq!(response.pm2_5, Some(40.0));
+        assert_eq!(response.pm10, None);
+        assert_eq!(response.us_epa_index, Some(112));
+        assert_eq!(response.european_index, Some(4));
+
+        let status = service.get_air_quality(request("alpha")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unimplemented);
+    }
+
+    #[tokio::test]
+    async fn test_forecast_attaches_astronomy_on_request() {
+        let service = chained_service(vec![("alpha", StubProvider::new("Alpha"))]);
+        let request = |include_astronomy: bool| Request::new(ForecastRequest {
+            latitude: 40.0,
+            longitude: -74.0,
+            days: 2,
+            provider: String::new(),
+            client_id: "test_client".to_string(),
+            units: None,
+            include_astronomy,
+        });
+
+        let response = service.get_forecast(request(false)).await.unwrap().into_inner();
+        assert!(response.forecasts.iter().all(|day| day.astronomy.is_none()));
+
+        let response = service.get_forecast(request(true)).await.unwrap().into_inner();
+        let first = response.forecasts[0].astronomy.as_ref().unwrap();
+        let second = response.forecasts[1].astronomy.as_ref().unwrap();
+        assert!(first.sunrise.unwrap() < first.solar_noon && first.solar_noon < first.sunset.unwrap());
+        // One day apart, give or take the change in day length
+        assert!((second.solar_noon - first.solar_noon - 86_400).abs() < 60);
+    }
+
+    fn astronomy_request(latitude: f64, date: &str) -> Request<AstronomyRequest> {
+        Request::new(AstronomyRequest {
+            latitude,
+            longitude: -0.1278,
+            date: date.to_string(),
+            client_id: "test_client".to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_astronomy_for_date() {
+        let service = stub_service(true);
+
+        let response = service.get_astronomy(astronomy_request(51.5074, "2024-03-25")).await.unwrap().into_inner();
+        assert_eq!(response.date, "2024-03-25");
+        let day = response.astronomy.unwrap();
+        assert_eq!(day.moon_phase(), crate::proto::weather::MoonPhase::FullMoon);
+        assert!(day.sunrise.is_some() && day.moonrise.is_some());
+
+        let response = service.get_astronomy(astronomy_request(51.5074, "")).await.unwrap().into_inner();
+        assert_eq!(response.date.len(), "YYYY-MM-DD".len());
+        assert!(response.astronomy.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_astronomy_new_moon_is_not_the_default() {
+        let service = stub_service(true);
+
+        let response = service.get_astronomy(astronomy_request(51.5074, "2024-04-08")).await.unwrap().into_inner();
+        let day = response.astronomy.unwrap();
+        assert_eq!(day.moon_phase(), crate::proto::weather::MoonPhase::NewMoon);
+        assert_ne!(day.moon_phase, crate::proto::weather::MoonPhase::Unspecified as i32);
+    }
+
+    #[tokio::test]
+    async fn test_astronomy_rejects_bad_input() {
+        let service = stub_service(true);
+
+        let status = service.get_astronomy(astronomy_request(51.5, "25/03/2024")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let status = service.get_astronomy(astronomy_request(91.0, "2024-03-25")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    fn historical_request(provider: &str, start_date: &str, end_date: &str) -> Request<HistoricalRequest> {
+        Request::new(HistoricalRequest {
+            latitude: 51.5,
+            longitude: -0.12,
+            provider: provider.to_string(),
+            start_date: start_date.to_string(),
+            end_date: end_date.to_string(),
+            client_id: "test_client".to_string(),
+            units: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_historical_covers_range_from_provider_with_archive() {
+        let service = chained_service(vec![
+            ("alpha", StubProvider::new("Alpha")),
+            ("beta", StubProvider::new("Beta").with_history()),
+        ]);
+
+        let response = service
+            .get_historical(historical_request("", "2024-02-28", "2024-03-01"))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.provider, "Beta");
+        assert!(!response.fallback_used);
+        let dates: Vec<_> = response.days.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(dates, ["2024-02-28", "2024-02-29", "2024-03-01"]);
+        assert_eq!(response.days[0].max_temp, 25.0);
+        assert_eq!(response.days[0].condition_code(), crate::proto::weather::ConditionCode::Clear);
+
+        let single = service
+            .get_historical(historical_request("", "2024-03-10", ""))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(single.days.len(), 1);
+
+        let status = service.get_historical(historical_request("alpha", "2024-03-10", "")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unimplemented);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_historical_fetches_a_few_days_at_a_time() {
+        let stub = Arc::new(StubProvider::new("Beta").with_history().with_delay(Duration::from_secs(1)));
+        let mut registry = ProviderRegistry::new();
+        registry.register(ProviderEntry {
+            id: "beta".to_string(),
+            display_name: "Beta".to_string(),
+            aliases: Vec::new(),
+            enabled: true,
+            provider: stub.clone(),
+        }).unwrap();
+        registry.set_call_timeout(Duration::from_secs(60));
+        let service = WeatherServiceImpl::new(registry);
+
+        let started = tokio::time::Instant::now();
+        let response = service
+            .get_historical(historical_request("beta", "2024-03-01", "2024-03-08"))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.days.len(), 8);
+        assert_eq!(response.days[7].date, "2024-03-08");
+        // Eight one-second lookups, HISTORICAL_CONCURRENCY at a time
+        assert_eq!(started.elapsed().as_secs(), (8 / HISTORICAL_CONCURRENCY) as u64);
+        assert_eq!(stub.calls(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_historical_rejects_bad_ranges() {
+        let service = chained_service(vec![("beta", StubProvider::new("Beta").with_history())]);
+        let tomorrow = dates::local_date(unix_now() + 86400, 0);
+
+        for (start, end) in [
+            ("2024-03-10", "2024-03-09"),
+            ("2024-01-01", "2024-03-01"),
+            ("10/03/2024", ""),
+            ("2023-02-29", ""),
+            ("2023-04-30", "2023-04-31"),
+            (tomorrow.as_str(), ""),
+        ] {
+            let status = service.get_historical(historical_request("", start, end)).await.unwrap_err();
+            assert_eq!(status.code(), tonic::Code::InvalidArgument, "{} to {}", start, end);
+        }
+    }
+
+    fn watch_request(locations: Vec<(f64, f64)>) -> Request<WatchWeatherRequest> {
+        Request::new(WatchWeatherRequest {
+            locations: locations
+                .into_iter()
+                .map(|(latitude, longitude)| Coordinates { latitude, longitude })
+                .collect(),
+            provider: String::new(),
+            client_id: "test_client".to_string(),
+            units: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_watch_sends_current_weather_per_location() {
+        let service = chained_service(vec![("alpha", StubProvider::new("Alpha"))]);
+        let mut stream = service
+            .watch_weather(watch_request(vec![(51.5, -0.12), (48.85, 2.35)]))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let mut seen = Vec::new();
+        for _ in 0..2 {
+            let update = stream.next().await.unwrap().unwrap();
+            let weather = update.weather.unwrap();
+            assert_eq!(weather.provider, "Alpha");
+            assert_eq!(weather.temperature, 20.0);
+            seen.push(update.location.unwrap().latitude);
+        }
+        seen.sort_by(f64::total_cmp);
+        assert_eq!(seen, [48.85, 51.5]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_watch_sees_upstream_changes_despite_cache() {
+        let stub = Arc::new(StubProvider::new("Alpha").with_warming(2.0));
+        let mut registry = ProviderRegistry::new();
+        registry.register(ProviderEntry {
+            id: "alpha".to_string(),
+            display_name: "Alpha".to_string(),
+            aliases: Vec::new(),
+            enabled: true,
+            provider: stub.clone(),
+        }).unwrap();
+        registry.set_fallback_chain(vec!["alpha".to_string()]).unwrap();
+        let upstream = registry.clone();
+        let cache = CacheConfig { current_ttl_secs: 3600, ..Default::default() };
+        registry.wrap_providers(|provider| -> Arc<dyn WeatherProvider> {
+            Arc::new(CachedProvider::new(provider, &cache))
+        });
+        let watch = WatchConfig { poll_interval_secs: 60, ..Default::default() };
+        let service = WeatherServiceImpl::new(registry).with_watch(upstream, &watch);
+
+        let mut stream = service
+            .watch_weather(watch_request(vec![(51.5, -0.12)]))
+            .await
+            .unwrap()
+            .into_inner();
+        let first = stream.next().await.unwrap().unwrap().weather.unwrap();
+        assert_eq!(first.temperature, 20.0);
+
+        // The next poll, one interval on, reaches the provider despite the cache
+        let second = tokio::time::timeout(Duration::from_secs(61), stream.next())
+            .await
+            .expect("no update within one poll interval")
+            .unwrap()
+            .unwrap()
+            .weather
+            .unwrap();
+        assert_eq!(second.temperature, 22.0);
+        assert_eq!(stub.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_watch_rejects_bad_subscriptions() {
+        let service = chained_service(vec![("alpha", StubProvider::new("Alpha"))]);
+
+        let status = service.watch_weather(watch_request(Vec::new())).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let status = service.watch_weather(watch_request(vec![(95.0, 0.0)])).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let too_many = vec![(1.0, 1.0); WatchConfig::default().max_locations + 1];
+        let status = service.watch_weather(watch_request(too_many)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    fn batch_request(locations: Vec<(f64, f64)>, provider: &str) -> Request<BatchWeatherRequest> {
+        Request::new(BatchWeatherRequest {
+            locations: locations
+                .into_iter()
+                .map(|(latitude, longitude)| Coordinates { latitude, longitude })
+                .collect(),
+            provider: provider.to_string(),
+            client_id: "test_client".to_string(),
+            units: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_batch_weather_in_request_order() {
+        let service = chained_service(vec![
+            ("alpha", StubProvider::new("Alpha").with_delay(Duration::from_millis(5))),
+        ]);
+        let locations: Vec<_> = (0..20).map(|i| (i as f64, -(i as f64))).collect();
+
+        let response = service
+            .get_weather_for_locations(batch_request(locations, ""))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.results.len(), 20);
+        for (i, result) in response.results.iter().enumerate() {
+            assert_eq!(result.location.as_ref().unwrap().latitude, i as f64);
+            assert_eq!(result.weather.as_ref().unwrap().provider, "Alpha");
+            assert!(result.error.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_weather_reports_failures_per_location() {
+        let service = chained_service(vec![("alpha", StubProvider::new("Alpha").failing())]);
+
+        let response = service
+            .get_weather_for_locations(batch_request(vec![(1.0, 2.0), (3.0, 4.0)], ""))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.results.len(), 2);
+        for result in &response.results {
+            assert!(result.weather.is_none());
+            assert!(result.error.contains("Alpha is unavailable"));
+            assert_eq!(result.error_code, tonic::Code::Unavailable as i32);
+        }
+
+        let response = service
+            .get_weather_for_locations(batch_request(vec![(1.0, 2.0)], "nowhere"))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.results[0].error_code, tonic::Code::InvalidArgument as i32);
+
+        let too_many = vec![(1.0, 2.0); MAX_BATCH_LOCATIONS + 1];
+        let status = service.get_weather_for_locations(batch_request(too_many, "")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    fn search_request(query: &str, limit: u32) -> Request<SearchLocationsRequest> {
+        Request::new(SearchLocationsRequest {
+            client_id: "test_client".to_string(),
+            query: query.to_string(),
+            limit,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_search_locations_uses_first_answering_geocoder() {
+        let geocoders = GeocoderChain::new(
+            vec![Arc::new(StubGeocoder::new("Down").failing()), Arc::new(StubGeocoder::new("Up"))],
+            Duration::from_millis(50),
+        );
+        let service = stub_service(true).with_geocoders(geocoders);
+
+        let response = service.search_locations(search_request(" London ", 0)).await.unwrap().into_inner();
+
+        assert_eq!(response.source, "Up");
+        assert_eq!(response.places.len(), 2);
+        assert_eq!(response.places[0].name, "London");
+        assert_eq!(response.places[0].state, "England");
+        assert_eq!(response.places[0].country, "GB");
+    }
+
+    #[tokio::test]
+    async fn test_search_locations_rejects_bad_requests() {
+        let service = stub_service(true);
+        let status = service.search_locations(search_request("london", 5)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unimplemented);
+
+        let geocoders = GeocoderChain::new(vec![Arc::new(StubGeocoder::new("Up"))], Duration::from_millis(50));
+        let service = stub_service(true).with_geocoders(geocoders);
+        let status = service.search_locations(search_request("   ", 5)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    fn geocoders(geocoder: StubGeocoder) -> (Arc<StubGeocoder>, GeocoderChain) {
+        let geocoder = Arc::new(geocoder);
+        (geocoder.clone(), GeocoderChain::new(vec![geocoder], Duration::from_millis(50)))
+    }
+
+    #[tokio::test]
+    async fn test_weather_names_place_from_payload_before_geocoders() {
+        let (geocoder, chain) = geocoders(StubGeocoder::new("Geo"));
+        let paris = Locality {
+            name: "Paris".to_string(),
+            region: None,
+            country: "France".to_string(),
+            timezone: Some("Europe/Paris".to_string()),
+        };
+        let service = chained_service(vec![
+            ("alpha", StubProvider::new("Alpha").with_locality(paris)),
+            ("beta", StubProvider::new("Beta")),
+        ])
+        .with_geocoders(chain);
+        let request = |provider: &str| Request::new(WeatherRequest {
+            latitude: 48.85,
+            longitude: 2.35,
+            provider: provider.to_string(),
+            client_id: "test_client".to_string(),
+            units: None,
+        });
+
+        let response = service.get_current_weather(request("alpha")).await.unwrap().into_inner();
+        let location = response.location.unwrap();
+        assert_eq!(location.name, "Paris");
+        assert_eq!(location.region, "");
+        assert_eq!(location.timezone, "Europe/Paris");
+        assert_eq!(geocoder.calls(), 0);
+
+        // Beta's payload names no place, so the geocoder fills in
+        let response = service.get_current_weather(request("beta")).await.unwrap().into_inner();
+        assert_eq!(response.location.unwrap().name, "London");
+        assert_eq!(geocoder.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_forecast_location_from_geocoders_when_known() {
+        let (_, chain) = geocoders(StubGeocoder::new("Geo"));
+        let service = chained_service(vec![("alpha", StubProvider::new("Alpha"))]).with_geocoders(chain);
+        let request = || Request::new(ForecastRequest {
+            latitude: 51.5,
+            longitude: -0.12,
+            days: 2,
+            ..Default::default()
+        });
+
+        let response = service.get_forecast(request()).await.unwrap().into_inner();
+        assert_eq!(response.location.unwrap().timezone, "Europe/London");
+
+        // A failing or missing geocoder never fails the forecast itself
+        let (_, chain) = geocoders(StubGeocoder::new("Geo").failing());
+        let service = chained_service(vec![("alpha", StubProvider::new("Alpha"))]).with_geocoders(chain);
+        let response = service.get_forecast(request()).await.unwrap().into_inner();
+        assert!(response.location.is_none());
+        let service = chained_service(vec![("alpha", StubProvider::new("Alpha"))]);
+        assert!(service.get_forecast(request()).await.unwrap().into_inner().location.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_forecast_names_place_from_payload_before_geocoders() {
+        let (geocoder, chain) = geocoders(StubGeocoder::new("Geo"));
+        let service = chained_service(vec![("alpha", StubProvider::new("Alpha").with_locality(sample_locality()))])
+            .with_geocoders(chain);
+
+        let response = service
+            .get_forecast(Request::new(ForecastRequest {
+                latitude: 51.5,
+                longitude: -0.12,
+                days: 2,
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.location.unwrap().name, sample_locality().name);
+        assert_eq!(geocoder.calls(), 0);
+    }
+
+    fn reverse_request(latitude: f64, longitude: f64) -> Request<ReverseGeocodeRequest> {
+        Request::new(ReverseGeocodeRequest {
+            client_id: "test_client".to_string(),
+            latitude,
+            longitude,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_reverse_geocode() {
+        let (_, chain) = geocoders(StubGeocoder::new("Geo"));
+        let service = stub_service(true).with_geocoders(chain);
+
+        let response = service.reverse_geocode(reverse_request(51.5, -0.12)).await.unwrap().into_inner();
+        assert_eq!(response.source, "Geo");
+        assert_eq!(response.location.unwrap(), describe_locality(sample_locality()));
+
+        let status = service.reverse_geocode(reverse_request(91.0, 0.0)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_reverse_geocode_without_a_place() {
+        let (_, chain) = geocoders(StubGeocoder::new("Geo").without_places());
+        let service = stub_service(true).with_geocoders(chain);
+        let status = service.reverse_geocode(reverse_request(0.0, -30.0)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        let status = stub_service(true).reverse_geocode(reverse_request(0.0, -30.0)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unimplemented);
+    }
+}
@@ -0,0 +1,3 @@
+mod weather_store;
+
+pub use weather_store::{unix_now, Stored, WeatherStore};
\ No newline at end of file
@@ -0,0 +1,356 @@
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{de::DeserializeOwned, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::Row;
+use crate::error::{AppError, AppResult};
+use crate::providers::{CurrentWeather, DayForecast, HistoricalDay};
+use tracing::{info, warn};
+
+const KIND_CURRENT: &str = "current";
+const KIND_FORECAST: &str = "forecast";
+
+/// A persisted provider response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stored<T> {
+    pub value: T,
+    pub provider: String,
+    /// Unix seconds at which the provider returned the data.
+    pub fetched_at: i64,
+}
+
+impl<T> Stored<T> {
+    pub fn age_secs(&self) -> i64 {
+        unix_now() - self.fetched_at
+    }
+}
+
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// SQLite-backed store of the latest response per provider and location.
+pub struct WeatherStore {
+    pool: SqlitePool,
+    scale: f64,
+}
+
+impl WeatherStore {
+    /// Opens (creating if needed) the database and applies pending migrations.
+    pub async fn connect(database_url: &str, precision: u32) -> AppResult<Self> {
+        let options = SqliteConnectOptions::from_str(database_url)?.create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect_with(options)
+            .await?;
+
+        sqlx::migrate!("./migrations").run(&pool).await?;
+        info!(database_url = %database_url, "Weather store ready");
+
+        Ok(Self {
+            pool,
+            scale: 10f64.powi(precision as i32),
+        })
+    }
+
+    fn quantize(&self, latitude: f64, longitude: f64) -> (i64, i64) {
+        (
+            (latitude * self.scale).round() as i64,
+            (longitude * self.scale).round() as i64,
+        )
+    }
+
+    pub async fn put_current(
+        &self,
+        provider: &str,
+        latitude: f64,
+        longitude: f64,
+        weather: &CurrentWeather,
+    ) -> AppResult<()> {
+        self.put(provider, KIND_CURRENT, latitude, longitude, 0, weather).await
+    }
+
+    pub async fn put_forecast(
+        &self,
+        provider: &str,
+        latitude: f64,
+        longitude: f64,
+        days: i32,
+        forecast: &[DayForecast],
+    ) -> AppResult<()> {
+        self.put(provider, KIND_FORECAST, latitude, longitude, days, forecast).await
+    }
+
+    /// Latest current weather stored for `provider` at this location.
+    pub async fn get_current(
+        &self,
+        provider: &str,
+        latitude: f64,
+        longitude: f64,
+    ) -> AppResult<Option<Stored<CurrentWeather>>> {
+        self.get(Some(provider), KIND_CURRENT, latitude, longitude, 0).await
+    }
+
+    pub async fn get_forecast(
+        &self,
+        provider: &str,
+        latitude: f64,
+        longitude: f64,
+        days: i32,
+    ) -> AppResult<Option<Stored<Vec<DayForecast>>>> {
+        self.get(Some(provider), KIND_FORECAST, latitude, longitude, days).await
+    }
+
+    /// Newest current weather stored by any provider at this location.
+    pub async fn latest_current(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> AppResult<Option<Stored<CurrentWeather>>> {
+        self.get(None, KIND_CURRENT, latitude, longitude, 0).await
+    }
+
+    pub async fn latest_forecast(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        days: i32,
+    ) -> AppResult<Option<Stored<Vec<DayForecast>>>> {
+        self.get(None, KIND_FORECAST, latitude, longitude, days).await
+    }
+
+    pub async fn put_historical(
+        &self,
+        provider: &str,
+        latitude: f64,
+        longitude: f64,
+        day: &HistoricalDay,
+    ) -> AppResult<()> {
+        let (lat, lon) = self.quantize(latitude, longitude);
+        let payload = serde_json::to_string(day)
+            .map_err(|e| AppError::Storage(format!("Failed to encode history payload: {}", e)))?;
+
+        sqlx::query(
+            "INSERT INTO weather_history (provider, latitude, longitude, date, payload, fetched_at)
+             VALUES (?, ?, ?, ?, ?, ?)
+             ON CONFLICT (provider, latitude, longitude, date)
+             DO UPDATE SET payload = excluded.payload, fetched_at = excluded.fetched_at",
+        )
+        .bind(provider)
+        .bind(lat)
+        .bind(lon)
+        .bind(&day.date)
+        .bind(payload)
+        .bind(unix_now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Observed weather stored for `provider` on `date` at this location.
+    pub async fn get_historical(
+        &self,
+        provider: &str,
+        latitude: f64,
+        longitude: f64,
+        date: &str,
+    ) -> AppResult<Option<Stored<HistoricalDay>>> {
+        let (lat, lon) = self.quantize(latitude, longitude);
+        let row = sqlx::query(
+            "SELECT provider, payload, fetched_at FROM weather_history
+             WHERE provider = ? AND latitude = ? AND longitude = ? AND date = ?",
+        )
+        .bind(provider)
+        .bind(lat)
+        .bind(lon)
+        .bind(date)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let payload: String = row.try_get("payload")?;
+        match serde_json::from_str(&payload) {
+            Ok(value) => Ok(Some(Stored {
+                value,
+                provider: row.try_get("provider")?,
+                fetched_at: row.try_get("fetched_at")?,
+            })),
+            Err(e) => {
+                warn!(error = %e, "Discarding unreadable stored history");
+                Ok(None)
+            }
+        }
+    }
+
+    async fn put<T: Serialize + ?Sized>(
+        &self,
+        provider: &str,
+        kind: &str,
+        latitude: f64,
+        longitude: f64,
+        days: i32,
+        value: &T,
+    ) -> AppResult<()> {
+        let (lat, lon) = self.quantize(latitude, longitude);
+        let payload = serde_json::to_string(value)
+            .map_err(|e| AppError::Storage(format!("Failed to encode {} payload: {}", kind, e)))?;
+
+        sqlx::query(
+            "INSERT INTO weather_cache (provider, kind, latitude, longitude, days, payload, fetched_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT (provider, kind, latitude, longitude, days)
+             DO UPDATE SET payload = excluded.payload, fetched_at = excluded.fetched_at",
+        )
+        .bind(provider)
+        .bind(kind)
+        .bind(lat)
+        .bind(lon)
+        .bind(days)
+        .bind(payload)
+        .bind(unix_now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        provider: Option<&str>,
+        kind: &str,
+        latitude: f64,
+        longitude: f64,
+        days: i32,
+    ) -> AppResult<Option<Stored<T>>> {
+        let (lat, lon) = self.quantize(latitude, longitude);
+        let row = sqlx::query(
+            "SELECT provider, payload, fetched_at FROM weather_cache
+             WHERE kind = ? AND latitude = ? AND longitude = ? AND days = ?
+               AND (? IS NULL OR provider = ?)
+             ORDER BY fetched_at DESC
+             LIMIT 1",
+        )
+        .bind(kind)
+        .bind(lat)
+        .bind(lon)
+        .bind(days)
+        .bind(provider)
+        .bind(provider)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let payload: String = row.try_get("payload")?;
+        match serde_json::from_str(&payload) {
+            Ok(value) => Ok(Some(Stored {
+                value,
+                provider: row.try_get("provider")?,
+                fetched_at: row.try_get("fetched_at")?,
+            })),
+            Err(e) => {
+                // Rows written by an older payload layout are treated as missing
+                warn!(kind, error = %e, "Discarding unreadable stored payload");
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::ConditionCode;
+    use crate::tests::support::sample_weather;
+
+    async fn temp_store(dir: &tempfile::TempDir) -> WeatherStore {
+        let url = format!("sqlite://{}", dir.path().join("weather.db").display());
+        WeatherStore::connect(&url, 2).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_current_round_trip_survives_reconnect() {
+        let dir = tempfile::tempdir().unwrap();
+        let weather = sample_weather();
+        {
+            let store = temp_store(&dir).await;
+            store.put_current("OpenWeather", 51.5074, -0.1278, &weather).await.unwrap();
+        }
+
+        let store = temp_store(&dir).await;
+        let stored = store.get_current("OpenWeather", 51.51, -0.13).await.unwrap().unwrap();
+        assert_eq!(stored.provider, "OpenWeather");
+        assert_eq!(stored.value.temperature, weather.temperature);
+        assert!(stored.age_secs() <= 1);
+
+        assert!(store.get_current("WeatherAPI", 51.51, -0.13).await.unwrap().is_none());
+        assert!(store.get_current("OpenWeather", 48.85, 2.35).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_put_replaces_previous_response() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = temp_store(&dir).await;
+        let mut weather = sample_weather();
+
+        store.put_current("OpenWeather", 1.0, 2.0, &weather).await.unwrap();
+        weather.temperature = 5.0;
+        store.put_current("OpenWeather", 1.0, 2.0, &weather).await.unwrap();
+
+        let latest = store.latest_current(1.0, 2.0).await.unwrap().unwrap();
+        assert_eq!(latest.value.temperature, 5.0);
+        assert_eq!(latest.provider, "OpenWeather");
+    }
+
+    #[tokio::test]
+    async fn test_forecast_keyed_by_days() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = temp_store(&dir).await;
+        let forecast = vec![DayForecast {
+            date: "2024-03-20".to_string(),
+            max_temp: Some(25.0),
+            min_temp: Some(15.0),
+            condition: "Sunny".to_string(),
+            condition_code: ConditionCode::Clear,
+            precipitation_probability: Some(20.0),
+            precipitation_mm: Some(1.5),
+            locality: None,
+        }];
+
+        store.put_forecast("WeatherAPI", 1.0, 2.0, 1, &forecast).await.unwrap();
+
+        let stored = store.latest_forecast(1.0, 2.0, 1).await.unwrap().unwrap();
+        assert_eq!(stored.value[0].date, "2024-03-20");
+        assert_eq!(stored.value[0].precipitation_mm, Some(1.5));
+        assert!(store.get_forecast("WeatherAPI", 1.0, 2.0, 3).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_historical_keyed_by_date() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = temp_store(&dir).await;
+        let day = HistoricalDay {
+            date: "2024-03-10".to_string(),
+            max_temp: 11.8,
+            min_temp: 4.2,
+            avg_temp: Some(7.9),
+            condition: "Light rain".to_string(),
+            condition_code: ConditionCode::LightRain,
+            precipitation_mm: Some(3.6),
+            max_wind_speed: None,
+            avg_humidity: None,
+        };
+
+        store.put_historical("WeatherAPI", 1.0, 2.0, &day).await.unwrap();
+
+        let stored = store.get_historical("WeatherAPI", 1.0, 2.0, "2024-03-10").await.unwrap().unwrap();
+        assert_eq!(stored.value, day);
+        assert!(store.get_historical("WeatherAPI", 1.0, 2.0, "2024-03-11").await.unwrap().is_none());
+        assert!(store.get_historical("OpenMeteo", 1.0, 2.0, "2024-03-10").await.unwrap().is_none());
+    }
+}
@@ -1,3 +1,4 @@
 // Create this file if it doesn't exist
 pub mod provider_tests;
-pub mod service_tests;
\ No newline at end of file
+pub mod service_tests;
+pub mod support;
@@ -1,9 +1,11 @@
+use crate::config::Settings;
 use crate::providers::{OpenWeatherProvider, WeatherApiProvider, WeatherProvider};
 
 #[tokio::test]
 async fn test_providers_integration() {
-    let openweather = OpenWeatherProvider::new();
-    let weatherapi = WeatherApiProvider::new();
+    let settings = Settings::new().expect("Failed to load settings");
+    let openweather = OpenWeatherProvider::new(settings.providers.openweather_api_key);
+    let weatherapi = WeatherApiProvider::new(settings.providers.weatherapi_api_key);
 
     // Test both providers with the same coordinates
     let latitude = 40.7128;
@@ -1,3 +1,5 @@
+use crate::config::Settings;
+use crate::providers::ProviderRegistry;
 use crate::service::weather::WeatherServiceImpl;
 use crate::proto::weather::WeatherRequest;
 use crate::proto::weather::weather_service_server::WeatherService;
@@ -5,13 +7,17 @@ use tonic::Request;
 
 #[tokio::test]
 async fn test_weather_service_integration() {
-    let service = WeatherServiceImpl::new();
+    let settings = Settings::new().expect("Failed to load settings");
+    let registry = ProviderRegistry::from_settings(&settings.providers, &settings.cache)
+        .expect("Failed to build provider registry");
+    let service = WeatherServiceImpl::new(registry);
     
     let weather_request = Request::new(WeatherRequest {
         latitude: 40.7128,
         longitude: -74.0060,
         provider: "weatherapi".to_string(),
         client_id: "test_client".to_string(),
+        units: None,
     });
 
     let weather_response = service
@@ -0,0 +1,367 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use async_trait::async_trait;
+use crate::geocoding::{Geocoder, Locality, Place};
+use crate::providers::{
+    AirQuality, AlertSeverity, ConditionCode, CurrentWeather, DayForecast, HistoricalDay, HourlyForecast,
+    ProviderCapabilities, Unsupported, WeatherAlert, WeatherProvider,
+};
+
+/// Offline `WeatherProvider` returning canned data, for service and registry tests.
+pub struct StubProvider {
+    name: String,
+    weather: CurrentWeather,
+    fail: bool,
+    delay: Option<Duration>,
+    /// `None` when the stub has no alert feed
+    alerts: Option<Vec<WeatherAlert>>,
+    /// `None` when the stub has no air quality data
+    air_quality: Option<AirQuality>,
+    history: bool,
+    /// Degrees each current weather call reports above the previous one
+    warming: f64,
+    calls: AtomicUsize,
+}
+
+impl StubProvider {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            weather: sample_weather(),
+            fail: false,
+            delay: None,
+            alerts: None,
+            air_quality: None,
+            history: false,
+            warming: 0.0,
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    /// Every call returns an error.
+    pub fn failing(mut self) -> Self {
+        self.fail = true;
+        self
+    }
+
+    /// Every call sleeps for `delay` before answering.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Supports alerts, reporting `alerts` as in effect.
+    pub fn with_alerts(mut self, alerts: Vec<WeatherAlert>) -> Self {
+        self.alerts = Some(alerts);
+        self
+    }
+
+    /// Supports air quality, reporting `air`.
+    pub fn with_air_quality(mut self, air: AirQuality) -> Self {
+        self.air_quality = Some(air);
+        self
+    }
+
+    /// Supports historical weather, reporting the canned weather for any date.
+    pub fn with_history(mut self) -> Self {
+        self.history = true;
+        self
+    }
+
+    /// Number of weather or forecast calls received so far.
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    async fn respond(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+        if self.fail {
+            return Err(format!("{} is unavailable", self.name).into());
+        }
+        Ok(())
+    }
+
+    /// Names the place in its current weather, as WeatherAPI does.
+    pub fn with_locality(mut self, locality: Locality) -> Self {
+        self.weather.locality = Some(locality);
+        self
+    }
+
+    pub fn with_temperature(mut self, temperature: f64) -> Self {
+        self.weather.temperature = temperature;
+        self
+    }
+
+    /// Each current weather call reports `step` degrees warmer than the one
+    /// before, like an upstream whose data keeps changing.
+    pub fn with_warming(mut self, step: f64) -> Self {
+        self.warming = step;
+        self
+    }
+
+    /// Reports every optional field as unknown.
+    pub fn without_optional_fields(mut self) -> Self {
+        self.weather.uv_index = None;
+        self.weather.visibility = None;
+        self.weather.max_temp = None;
+        self.weather.min_temp = None;
+        self.weather.precipitation_probability = None;
+        self.weather.precipitation_mm = None;
+        self
+    }
+}
+
+pub fn sample_weather() -> CurrentWeather {
+    CurrentWeather {
+        temperature: 20.0,
+        humidity: 50.0,
+        condition: "Sunny".to_string(),
+        condition_code: ConditionCode::Clear,
+        is_day: Some(true),
+        wind_speed: 10.0,
+        wind_direction: 180.0,
+        uv_index: Some(5.0),
+        visibility: Some(10_000.0),
+        pressure: Some(1013.0),
+        country: "US".to_string(),
+        max_temp: Some(25.0),
+        min_temp: Some(15.0),
+        precipitation_probability: Some(30.0),
+        precipitation_mm: Some(0.0),
+        locality: None,
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for StubProvider {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            max_forecast_days: 7,
+            max_forecast_hours: 48,
+            supported_fields: vec!["temperature", "condition"],
+            supports_alerts: self.alerts.is_some(),
+            supports_air_quality: self.air_quality.is_some(),
+            supports_historical: self.history,
+        }
+    }
+
+    async fn get_current_weather(
+        &self,
+        _latitude: f64,
+        _longitude: f64,
+    ) -> Result<CurrentWeather, Box<dyn std::error::Error + Send + Sync>> {
+        self.respond().await?;
+        let earlier_calls = self.calls().saturating_sub(1) as f64;
+        Ok(CurrentWeather {
+            temperature: self.weather.temperature + self.warming * earlier_calls,
+            ..self.weather.clone()
+        })
+    }
+
+    async fn get_forecast(
+        &self,
+        _latitude: f64,
+        _longitude: f64,
+        days: i32,
+    ) -> Result<Vec<DayForecast>, Box<dyn std::error::Error + Send + Sync>> {
+        self.respond().await?;
+        Ok((0..days)
+            .map(|day| DayForecast {
+                date: format!("2024-03-{:02}", 20 + day),
+                max_temp: self.weather.max_temp.map(|t| t as f32),
+                min_temp: self.weather.min_temp.map(|t| t as f32),
+                condition: self.weather.condition.clone(),
+                condition_code: self.weather.condition_code,
+                precipitation_probability: self.weather.precipitation_probability.map(|p| p as f32),
+                precipitation_mm: self.weather.precipitation_mm.map(|p| p as f32),
+                locality: self.weather.locality.clone(),
+            })
+            .collect())
+    }
+
+    async fn get_hourly_forecast(
+        &self,
+        _latitude: f64,
+        _longitude: f64,
+        hours: i32,
+    ) -> Result<Vec<HourlyForecast>, Box<dyn std::error::Error + Send + Sync>> {
+        self.respond().await?;
+        Ok((0..hours)
+            .map(|hour| HourlyForecast {
+                // 2024-03-20T00:00:00Z
+                timestamp: 1710892800 + hour as i64 * 3600,
+                temperature: self.weather.temperature,
+                precipitation_probability: self.weather.precipitation_probability,
+                wind_speed: self.weather.wind_speed,
+                wind_direction: self.weather.wind_direction,
+                condition: self.weather.condition.clone(),
+                condition_code: self.weather.condition_code,
+                is_day: self.weather.is_day,
+            })
+            .collect())
+    }
+
+    async fn get_alerts(
+        &self,
+        _latitude: f64,
+        _longitude: f64,
+    ) -> Result<Vec<WeatherAlert>, Box<dyn std::error::Error + Send + Sync>> {
+        self.respond().await?;
+        match &self.alerts {
+            Some(alerts) => Ok(alerts.clone()),
+            None => Err(Box::new(Unsupported {
+                provider: self.name.clone(),
+                feature: "weather alerts",
+            })),
+        }
+    }
+
+    async fn get_air_quality(
+        &self,
+        _latitude: f64,
+        _longitude: f64,
+    ) -> Result<AirQuality, Box<dyn std::error::Error + Send + Sync>> {
+        self.respond().await?;
+        self.air_quality.clone().ok_or_else(|| {
+            Box::new(Unsupported {
+                provider: self.name.clone(),
+                feature: "air quality",
+            }) as Box<dyn std::error::Error + Send + Sync>
+        })
+    }
+
+    async fn get_historical(
+        &self,
+        _latitude: f64,
+        _longitude: f64,
+        date: &str,
+    ) -> Result<HistoricalDay, Box<dyn std::error::Error + Send + Sync>> {
+        self.respond().await?;
+        if !self.history {
+            return Err(Box::new(Unsupported {
+                provider: self.name.clone(),
+                feature: "historical weather",
+            }));
+        }
+        Ok(HistoricalDay {
+            date: date.to_string(),
+            max_temp: self.weather.max_temp.unwrap_or(self.weather.temperature),
+            min_temp: self.weather.min_temp.unwrap_or(self.weather.temperature),
+            avg_temp: Some(self.weather.temperature),
+            condition: self.weather.condition.clone(),
+            condition_code: self.weather.condition_code,
+            precipitation_mm: self.weather.precipitation_mm,
+            max_wind_speed: Some(self.weather.wind_speed),
+            avg_humidity: Some(self.weather.humidity),
+        })
+    }
+}
+
+pub fn sample_alert() -> WeatherAlert {
+    WeatherAlert {
+        event: "Flood Warning".to_string(),
+        severity: AlertSeverity::Severe,
+        headline: "Flood Warning until March 21".to_string(),
+        description: "River flooding is expected.".to_string(),
+        agency: Some("NWS Boston MA".to_string()),
+        // 2024-03-20T12:00:00Z to 2024-03-21T12:00:00Z
+        starts_at: Some(1710936000),
+        ends_at: Some(1711022400),
+    }
+}
+
+/// Offline `Geocoder` placing everything in London, for geocoding tests.
+pub struct StubGeocoder {
+    name: String,
+    fail: bool,
+    places: bool,
+    calls: AtomicUsize,
+}
+
+impl StubGeocoder {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            fail: false,
+            places: true,
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    /// Every lookup returns an error.
+    pub fn failing(mut self) -> Self {
+        self.fail = true;
+        self
+    }
+
+    /// Knows no places: searches and reverse lookups come back empty.
+    pub fn without_places(mut self) -> Self {
+        self.places = false;
+        self
+    }
+
+    /// Number of searches and reverse lookups received so far.
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    fn respond(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if self.fail {
+            return Err(format!("{} is unavailable", self.name).into());
+        }
+        Ok(())
+    }
+}
+
+pub fn sample_locality() -> Locality {
+    Locality {
+        name: "London".to_string(),
+        region: Some("England".to_string()),
+        country: "GB".to_string(),
+        timezone: Some("Europe/London".to_string()),
+    }
+}
+
+pub fn sample_place() -> Place {
+    Place {
+        name: "London".to_string(),
+        state: Some("England".to_string()),
+        country: "GB".to_string(),
+        latitude: 51.5073,
+        longitude: -0.1276,
+    }
+}
+
+#[async_trait]
+impl Geocoder for StubGeocoder {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    async fn search(
+        &self,
+        _query: &str,
+        limit: usize,
+    ) -> Result<Vec<Place>, Box<dyn std::error::Error + Send + Sync>> {
+        self.respond()?;
+        let found = if self.places { limit.min(2) } else { 0 };
+        Ok(std::iter::repeat_n(sample_place(), found).collect())
+    }
+
+    async fn reverse(
+        &self,
+        _latitude: f64,
+        _longitude: f64,
+    ) -> Result<Option<Locality>, Box<dyn std::error::Error + Send + Sync>> {
+        self.respond()?;
+        Ok(self.places.then(sample_locality))
+    }
+}